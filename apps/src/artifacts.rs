@@ -0,0 +1,256 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! On-disk artifacts of a publisher run, to audit, retry a submission, or
+//! debug a failed transaction without proving again.
+//!
+//! An output directory holds:
+//!
+//! * `power_modulus.receipt`: the local power_modulus receipt (bincode).
+//! * `is_even.receipt`: the composed is_even Groth16 receipt (bincode).
+//! * `journal.hex`: the ABI journal of the is_even receipt.
+//! * `seal.hex`: the seal, encoded with `groth16::encode`.
+//! * `calldata.json`: the `IEvenNumber.set` transaction to send.
+//! * `manifest.json`: the image IDs the receipts were proven against.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{ensure, Context, Result};
+use methods::{IS_EVEN_ID, POWER_MODULUS_ID};
+use risc0_zkvm::{sha::Digest, Receipt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{encode_seal, proving, set_calldata};
+
+const POWER_MODULUS_RECEIPT: &str = "power_modulus.receipt";
+const IS_EVEN_RECEIPT: &str = "is_even.receipt";
+const JOURNAL: &str = "journal.hex";
+const SEAL: &str = "seal.hex";
+const CALLDATA: &str = "calldata.json";
+const MANIFEST: &str = "manifest.json";
+
+/// Transaction publishing a composed receipt, as written to `calldata.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Calldata {
+    /// Address of the app contract, if known when the artifacts were written.
+    pub to: Option<String>,
+    /// Hex-encoded `IEvenNumber.set` calldata.
+    pub data: String,
+    /// Estimated gas of the transaction, if estimated for a dry run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_estimate: Option<u64>,
+}
+
+/// Image IDs of the guests the receipts were proven against.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Hex-encoded image ID of the power_modulus guest.
+    pub power_modulus_image_id: String,
+    /// Hex-encoded image ID of the is_even guest.
+    pub is_even_image_id: String,
+}
+
+impl Manifest {
+    /// Returns the manifest of the guests built into this binary.
+    pub fn current() -> Self {
+        Self {
+            power_modulus_image_id: Digest::from(POWER_MODULUS_ID).to_string(),
+            is_even_image_id: Digest::from(IS_EVEN_ID).to_string(),
+        }
+    }
+}
+
+/// Directory holding the artifacts of a publisher run.
+pub struct Artifacts {
+    dir: PathBuf,
+}
+
+impl Artifacts {
+    /// Opens the given output directory, creating it if needed.
+    pub fn create(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        let artifacts = Self { dir: dir.into() };
+        artifacts.write_json(MANIFEST, &Manifest::current())?;
+        Ok(artifacts)
+    }
+
+    /// Opens an existing output directory, checking that its receipts were
+    /// proven against the guests built into this binary.
+    pub fn open(dir: &Path) -> Result<Self> {
+        let artifacts = Self { dir: dir.into() };
+        let manifest: Manifest = artifacts.read_json(MANIFEST)?;
+        ensure!(
+            manifest == Manifest::current(),
+            "artifacts in {} were produced for other guests: {manifest:?}, expected {:?}",
+            dir.display(),
+            Manifest::current()
+        );
+        Ok(artifacts)
+    }
+
+    /// Writes the local power_modulus receipt, removing the artifacts derived
+    /// from any previous one, so that they cannot be published by mistake.
+    pub fn write_local_receipt(&self, receipt: &Receipt) -> Result<()> {
+        for name in [IS_EVEN_RECEIPT, JOURNAL, SEAL, CALLDATA] {
+            self.remove(name)?;
+        }
+        proving::write_receipt(&self.path(POWER_MODULUS_RECEIPT), receipt)
+    }
+
+    /// Writes the composed is_even receipt, along with its journal, seal, and
+    /// the calldata publishing it to the given contract.
+    pub fn write_composed_receipt(&self, receipt: &Receipt, contract: Option<&str>) -> Result<()> {
+        proving::write_receipt(&self.path(IS_EVEN_RECEIPT), receipt)?;
+        self.write(JOURNAL, hex_string(&receipt.journal.bytes))?;
+        self.write(SEAL, hex_string(&encode_seal(receipt)?))?;
+        self.write_calldata(&Calldata::new(contract, &set_calldata(receipt)?))
+    }
+
+    /// Writes the transaction publishing the composed receipt.
+    pub fn write_calldata(&self, calldata: &Calldata) -> Result<()> {
+        self.write_json(CALLDATA, calldata)
+    }
+
+    /// Reads the local power_modulus receipt.
+    pub fn read_local_receipt(&self) -> Result<Receipt> {
+        proving::read_receipt(&self.path(POWER_MODULUS_RECEIPT))
+    }
+
+    /// Reads the composed is_even receipt.
+    pub fn read_composed_receipt(&self) -> Result<Receipt> {
+        proving::read_receipt(&self.path(IS_EVEN_RECEIPT))
+    }
+
+    /// Reads the transaction publishing the composed receipt.
+    pub fn read_calldata(&self) -> Result<Calldata> {
+        self.read_json(CALLDATA)
+    }
+
+    /// Reads the hex-encoded journal of the composed receipt.
+    pub fn read_journal(&self) -> Result<Vec<u8>> {
+        self.read_hex(JOURNAL)
+    }
+
+    /// Reads the hex-encoded seal of the composed receipt.
+    pub fn read_seal(&self) -> Result<Vec<u8>> {
+        self.read_hex(SEAL)
+    }
+
+    /// Returns whether the artifact with the given name was written.
+    fn exists(&self, name: &str) -> bool {
+        self.path(name).exists()
+    }
+
+    /// Returns whether the local power_modulus receipt was written.
+    pub fn has_local_receipt(&self) -> bool {
+        self.exists(POWER_MODULUS_RECEIPT)
+    }
+
+    /// Returns whether the composed is_even receipt was written.
+    pub fn has_composed_receipt(&self) -> bool {
+        self.exists(IS_EVEN_RECEIPT)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        let path = self.path(name);
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                Err(err).with_context(|| format!("removing {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&self, name: &str, contents: String) -> Result<()> {
+        let path = self.path(name);
+        fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))
+    }
+
+    fn write_json<T: Serialize>(&self, name: &str, value: &T) -> Result<()> {
+        self.write(name, serde_json::to_string_pretty(value)?)
+    }
+
+    fn read(&self, name: &str) -> Result<String> {
+        let path = self.path(name);
+        fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))
+    }
+
+    fn read_json<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
+        serde_json::from_str(&self.read(name)?)
+            .with_context(|| format!("decoding {}", self.path(name).display()))
+    }
+
+    fn read_hex(&self, name: &str) -> Result<Vec<u8>> {
+        decode_hex(&self.read(name)?)
+            .with_context(|| format!("decoding {}", self.path(name).display()))
+    }
+}
+
+impl Calldata {
+    /// Creates the transaction sending the given calldata to the contract.
+    pub fn new(contract: Option<&str>, data: &[u8]) -> Self {
+        Self {
+            to: contract.map(str::to_string),
+            data: hex_string(data),
+            gas_estimate: None,
+        }
+    }
+
+    /// Returns the decoded calldata.
+    pub fn bytes(&self) -> Result<Vec<u8>> {
+        decode_hex(&self.data).context("decoding calldata")
+    }
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    let s = s.trim();
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+
+    use super::*;
+
+    #[test]
+    fn removes_artifacts_of_previous_receipt() {
+        let dir = tempfile::tempdir().unwrap();
+        let artifacts = Artifacts::create(dir.path()).unwrap();
+        for name in [IS_EVEN_RECEIPT, JOURNAL, SEAL, CALLDATA] {
+            fs::write(artifacts.path(name), "stale").unwrap();
+        }
+
+        let claim = ReceiptClaim::ok(POWER_MODULUS_ID, vec![]);
+        let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), vec![]);
+        artifacts.write_local_receipt(&receipt).unwrap();
+
+        assert!(artifacts.has_local_receipt());
+        for name in [IS_EVEN_RECEIPT, JOURNAL, SEAL, CALLDATA] {
+            assert!(!artifacts.exists(name), "{name}");
+        }
+        Artifacts::open(dir.path()).unwrap();
+    }
+}
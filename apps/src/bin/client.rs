@@ -0,0 +1,58 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// This application is the client-side half of the `publisher`: it proves the
+// power_modulus guest over the private input on this machine, and exports the
+// succinct receipt for the `server` to compose. The private input never leaves
+// this machine; only the receipt does. It reveals `n`, `e` and `x^e mod n`, and with
+// --salt-file the commitment `sha256(x || salt)`, which the contract stores.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use apps::{
+    cli::{InputArgs, LocalProverArgs},
+    prover::init_dev_mode,
+    proving::{prove_power_modulus, write_receipt},
+};
+use clap::Parser;
+
+/// Arguments of the client CLI.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    prover: LocalProverArgs,
+
+    /// File to write the power_modulus receipt to, for the server to compose.
+    #[clap(long, default_value = "power_modulus.receipt")]
+    receipt: PathBuf,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+    init_dev_mode(&[args.prover.local_prover]);
+
+    //  Explicitly prove using private inputs
+    let receipt = prove_power_modulus(&args.input.input()?, args.prover.local_prover)?;
+
+    write_receipt(&args.receipt, &receipt)?;
+    log::info!("Wrote power_modulus receipt to {}", args.receipt.display());
+
+    Ok(())
+}
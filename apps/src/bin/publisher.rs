@@ -15,165 +15,353 @@
 // This application demonstrates how to send an off-chain proof request
 // to the Bonsai proving service and publish the received proofs directly
 // to your deployed app contract.
+//
+// Each stage is a subcommand reading the artifacts of the previous stage from,
+// and writing its own to, an output directory, so that stages can run on
+// different machines or be retried independently. The `run` subcommand runs
+// all stages in one process.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use apps::{
+    artifacts::{Artifacts, Calldata},
+    cli::{EthArgs, InputArgs, LocalProverArgs, RemoteProverArgs},
+    encode_seal, print_confirmation,
+    privacy::Privacy,
+    prover::{check_composable, init_dev_mode, ProverBackend},
+    proving::{compose, prove_power_modulus},
+    set_calldata,
+    signer::TxSigner,
+    TxSender,
+};
+use clap::{Parser, Subcommand};
+use composition_core::{IsEvenJournal, PowerModulusJournal};
+use methods::{IS_EVEN_ID, POWER_MODULUS_ID};
+use tokio::runtime::Runtime;
 
-use alloy_primitives::U256;
-use alloy_sol_types::{sol, SolInterface, SolValue};
-use anyhow::{Context, Result};
-use clap::Parser;
-use ethers::prelude::*;
-use methods::IS_EVEN_ELF;
-use methods::POWER_MODULUS_ELF;
-use risc0_ethereum_contracts::groth16;
-use risc0_zkvm::{default_prover, ExecutorEnv, LocalProver, Prover, ProverOpts, VerifierContext};
-
-// `IEvenNumber` interface automatically generated via the alloy `sol!` macro.
-sol! {
-    interface IEvenNumber {
-        function set(uint256 x, bytes calldata seal);
-    }
+/// Arguments of the publisher CLI.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
 }
 
-/// Wrapper of a `SignerMiddleware` client to send transactions to the given
-/// contract's `Address`.
-pub struct TxSender {
-    chain_id: u64,
-    client: SignerMiddleware<Provider<Http>, Wallet<k256::ecdsa::SigningKey>>,
-    contract: Address,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Prove the power_modulus guest locally, writing its receipt to the output directory
+    ProveLocal {
+        #[clap(flatten)]
+        input: InputArgs,
+
+        #[clap(flatten)]
+        prover: LocalProverArgs,
+
+        /// Directory to write the artifacts to.
+        #[clap(long)]
+        out_dir: PathBuf,
+    },
+    /// Compose the power_modulus receipt of the output directory into an is_even Groth16 receipt
+    Compose {
+        /// Directory holding the artifacts of `prove-local`.
+        #[clap(long)]
+        out_dir: PathBuf,
+
+        /// Application's contract address on Ethereum, recorded in the calldata artifact
+        #[clap(long)]
+        contract: Option<String>,
+
+        #[clap(flatten)]
+        prover: RemoteProverArgs,
+    },
+    /// Publish the composed receipt of the output directory to the app contract
+    Publish {
+        #[clap(flatten)]
+        eth: EthArgs,
+
+        /// Directory holding the artifacts of `compose`.
+        #[clap(long)]
+        out_dir: PathBuf,
+    },
+    /// Cancel a pending transaction by replacing it with a zero-value transfer to the sender itself
+    Cancel {
+        #[clap(flatten)]
+        eth: EthArgs,
+
+        /// Nonce of the transaction to cancel. Defaults to the lowest pending nonce.
+        #[clap(long)]
+        nonce: Option<u64>,
+    },
+    /// Verify the receipts of the output directory, and that the other artifacts match them
+    Verify {
+        /// Directory holding the artifacts to verify.
+        #[clap(long)]
+        out_dir: PathBuf,
+    },
+    /// Prove locally, compose and publish, end to end
+    Run {
+        #[clap(flatten)]
+        eth: EthArgs,
+
+        #[clap(flatten)]
+        input: InputArgs,
+
+        #[clap(flatten)]
+        local_prover: LocalProverArgs,
+
+        #[clap(flatten)]
+        remote_prover: RemoteProverArgs,
+
+        /// Directory to write the receipts, journal, seal, calldata and a manifest of image IDs to.
+        #[clap(long)]
+        out_dir: Option<PathBuf>,
+
+        /// Build and print the transaction, with its estimated gas, instead of sending it
+        #[clap(long)]
+        dry_run: bool,
+    },
 }
 
-impl TxSender {
-    /// Creates a new `TxSender`.
-    pub fn new(chain_id: u64, rpc_url: &str, private_key: &str, contract: &str) -> Result<Self> {
-        let provider = Provider::<Http>::try_from(rpc_url)?;
-        let wallet: LocalWallet = private_key.parse::<LocalWallet>()?.with_chain_id(chain_id);
-        let client = SignerMiddleware::new(provider.clone(), wallet.clone());
-        let contract = contract.parse::<Address>()?;
-
-        Ok(TxSender {
-            chain_id,
-            client,
+impl Command {
+    /// Returns the prover backends selected for the command.
+    fn prover_backends(&self) -> Vec<ProverBackend> {
+        match self {
+            Self::ProveLocal { prover, .. } => vec![prover.local_prover],
+            Self::Compose { prover, .. } => vec![prover.remote_prover],
+            Self::Run {
+                local_prover,
+                remote_prover,
+                ..
+            } => vec![local_prover.local_prover, remote_prover.remote_prover],
+            Self::Publish { .. } | Self::Cancel { .. } | Self::Verify { .. } => vec![],
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    // Parse CLI Arguments: The application starts by parsing command-line arguments provided by the user.
+    let args = Args::parse();
+    // Enable dev mode, if selected, before any thread is started.
+    init_dev_mode(&args.command.prover_backends());
+
+    match args.command {
+        Command::ProveLocal {
+            input,
+            prover,
+            out_dir,
+        } => {
+            let artifacts = Artifacts::create(&out_dir)?;
+            let receipt = prove_power_modulus(&input.input()?, prover.local_prover)?;
+            artifacts.write_local_receipt(&receipt)
+        }
+        Command::Compose {
+            out_dir,
             contract,
-        })
+            prover,
+        } => {
+            let artifacts = Artifacts::open(&out_dir)?;
+            let receipt = compose(artifacts.read_local_receipt()?, prover.remote_prover)?;
+            artifacts.write_composed_receipt(&receipt, contract.as_deref())
+        }
+        Command::Publish { eth, out_dir } => publish(&eth, &out_dir),
+        Command::Cancel { eth, nonce } => cancel(&eth, nonce),
+        Command::Verify { out_dir } => verify(&Artifacts::open(&out_dir)?),
+        Command::Run {
+            eth,
+            input,
+            local_prover,
+            remote_prover,
+            out_dir,
+            dry_run,
+        } => run(
+            &eth,
+            &input,
+            &local_prover,
+            &remote_prover,
+            out_dir.as_deref(),
+            dry_run,
+        ),
     }
+}
 
-    /// Send a transaction with the given calldata.
-    pub async fn send(&self, calldata: Vec<u8>) -> Result<Option<TransactionReceipt>> {
-        let tx = TransactionRequest::new()
-            .chain_id(self.chain_id)
-            .to(self.contract)
-            .from(self.client.address())
-            .data(calldata);
+/// Sends the transaction of the `calldata.json` artifact.
+fn publish(eth: &EthArgs, out_dir: &Path) -> Result<()> {
+    let runtime = Runtime::new()?;
+    let tx_sender = runtime.block_on(eth.tx_sender())?;
+    let calldata = Artifacts::open(out_dir)?.read_calldata()?;
+    if let Some(to) = &calldata.to {
+        let contract = format!("{:?}", tx_sender.contract());
+        if !to.eq_ignore_ascii_case(&contract) {
+            log::warn!("Calldata was built for contract {to}, publishing to {contract}");
+        }
+    }
 
-        log::info!("Transaction request: {:?}", &tx);
+    send(&runtime, &tx_sender, calldata.bytes()?, eth.force)
+}
 
-        let tx = self.client.send_transaction(tx, None).await?.await?;
+/// Cancels a pending transaction of the wallet.
+fn cancel(eth: &EthArgs, nonce: Option<u64>) -> Result<()> {
+    let runtime = Runtime::new()?;
+    let tx_sender = runtime.block_on(eth.cancel_tx_sender())?;
+    let receipt = runtime.block_on(tx_sender.cancel(nonce.map(Into::into)))?;
+    print_confirmation(&receipt);
+    Ok(())
+}
 
-        log::info!("Transaction receipt: {:?}", &tx);
+/// Verifies the receipts of the output directory, and checks that the journal,
+/// seal and calldata artifacts were derived from them.
+fn verify(artifacts: &Artifacts) -> Result<()> {
+    ensure!(
+        artifacts.has_local_receipt() || artifacts.has_composed_receipt(),
+        "no receipt to verify"
+    );
+
+    if artifacts.has_local_receipt() {
+        artifacts
+            .read_local_receipt()?
+            .verify(POWER_MODULUS_ID)
+            .context("verifying power_modulus receipt")?;
+        log::info!("power_modulus receipt verified");
+    }
 
-        Ok(tx)
+    if artifacts.has_composed_receipt() {
+        let receipt = artifacts.read_composed_receipt()?;
+        receipt
+            .verify(IS_EVEN_ID)
+            .context("verifying is_even receipt")?;
+        if artifacts.has_local_receipt() {
+            // The is_even receipt must compose the power_modulus receipt of the directory.
+            let local_receipt = artifacts.read_local_receipt()?;
+            let local = PowerModulusJournal::decode(&local_receipt.journal.bytes)
+                .context("decoding power_modulus journal")?;
+            let composed = IsEvenJournal::decode(&receipt.journal.bytes)
+                .context("decoding is_even journal")?;
+            ensure!(
+                composed == IsEvenJournal::from(local),
+                "is_even journal {composed:?} does not match the power_modulus journal {local:?}"
+            );
+        }
+        ensure!(
+            artifacts.read_journal()? == receipt.journal.bytes,
+            "journal does not match the is_even receipt"
+        );
+        ensure!(
+            artifacts.read_seal()? == encode_seal(&receipt)?,
+            "seal does not match the is_even receipt"
+        );
+        ensure!(
+            artifacts.read_calldata()?.bytes()? == set_calldata(&receipt)?,
+            "calldata does not match the is_even receipt"
+        );
+        log::info!("is_even receipt verified");
     }
-}
 
-/// Arguments of the publisher CLI.
-#[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
-struct Args {
-    /// Ethereum chain ID
-    #[clap(long)]
-    chain_id: u64,
-
-    /// Ethereum Node endpoint.
-    #[clap(long, env)]
-    eth_wallet_private_key: String,
-
-    /// Ethereum Node endpoint.
-    #[clap(long)]
-    rpc_url: String,
-
-    /// Application's contract address on Ethereum
-    #[clap(long)]
-    contract: String,
-
-    /// The input to provide to the LOCAL guest binary
-    #[clap(short, long)]
-    n: u64,
-    #[clap(short, long)]
-    e: u64,
-    #[clap(short, long)]
-    x: u64,
+    Ok(())
 }
 
-fn main() -> Result<()> {
-    env_logger::init();
-    // Parse CLI Arguments: The application starts by parsing command-line arguments provided by the user.
-    let args = Args::parse();
-
-    // Create a new transaction sender using the parsed arguments.
-    let tx_sender = TxSender::new(
-        args.chain_id,
-        &args.rpc_url,
-        &args.eth_wallet_private_key,
-        &args.contract,
-    )?;
+/// Runs all stages in one process, optionally keeping the artifacts.
+fn run(
+    eth: &EthArgs,
+    input: &InputArgs,
+    local_prover: &LocalProverArgs,
+    remote_prover: &RemoteProverArgs,
+    out_dir: Option<&Path>,
+    dry_run: bool,
+) -> Result<()> {
+    // Initialize the async runtime environment, serving the node connections while proving, and
+    // create a new transaction sender using the parsed arguments.
+    // A dry run only estimates the transaction, without any wallet credentials.
+    let runtime = Runtime::new()?;
+    let tx_sender = if dry_run {
+        runtime.block_on(eth.dry_run_tx_sender())?
+    } else {
+        runtime.block_on(eth.tx_sender())?
+    };
+    let artifacts = out_dir.map(Artifacts::create).transpose()?;
+
+    // Check that both provers are allowed, available and compatible, before proving anything.
+    let local_prover = local_prover.local_prover;
+    let remote_prover = remote_prover.remote_prover;
+    Privacy::Private.check(local_prover)?;
+    check_composable(local_prover, remote_prover)?;
+    local_prover.check_available()?;
+    remote_prover.check_available()?;
+
+    // Check that at least one RPC endpoint is healthy, before paying for the proofs.
+    runtime.block_on(tx_sender.check_health())?;
 
     // --------------- LOCAL CLIENT-SIDE ---------------
 
-    let local_input = (args.n, args.e, args.x);
-    let local_env = ExecutorEnv::builder().write(&local_input)?.build()?;
-
     //  Explicitly prove using private inputs
-    let local_receipt = LocalProver::new("local")
-        .prove(local_env, POWER_MODULUS_ELF)?
-        .receipt;
+    let local_receipt = prove_power_modulus(&input.input()?, local_prover)?;
+    if let Some(artifacts) = &artifacts {
+        artifacts.write_local_receipt(&local_receipt)?;
+    }
 
     // --------------- REMOTE SERVER-SIDE ---------------
 
-    // ABI encode input: Before sending the proof request to the Bonsai proving service,
-    // the input number is ABI-encoded to match the format expected by the guest code running in the zkVM.
-    let local_res: (u64, u64, u64) = local_receipt.journal.decode()?;
-    let remote_input = local_res.2.abi_encode();
-
-    let remote_env = ExecutorEnv::builder()
-        .add_assumption(local_receipt)
-        .write_slice(&remote_input)
-        .build()?;
-
-    // As we `export` the BONSAI env vars, default will use Boansi to prove:
-    let remote_receipt = default_prover()
-        .prove_with_ctx(
-            remote_env,
-            &VerifierContext::default(),
-            IS_EVEN_ELF,
-            &ProverOpts::groth16(),
-        )?
-        .receipt;
-
-    // Encode the seal with the selector.
-    let seal = groth16::encode(remote_receipt.inner.groth16()?.seal.clone())?;
-
-    // Extract the journal from the receipt.
-    let journal = remote_receipt.journal.bytes.clone();
-
-    // Decode Journal: Upon receiving the proof, the application decodes the journal to extract
-    // the verified number. This ensures that the number being submitted to the blockchain matches
-    // the number that was verified off-chain.
-    let x = U256::abi_decode(&journal, true).context("decoding journal data")?;
-
-    // Construct function call: Using the IEvenNumber interface, the application constructs
-    // the ABI-encoded function call for the set function of the EvenNumber contract.
-    // This call includes the verified number, the post-state digest, and the seal (proof).
-    let calldata = IEvenNumber::IEvenNumberCalls::set(IEvenNumber::setCall {
-        x,
-        seal: seal.into(),
-    })
-    .abi_encode();
-
-    // Initialize the async runtime environment to handle the transaction sending.
-    let runtime = tokio::runtime::Runtime::new()?;
+    // Compose the local receipt: its journal and the number to check are written in the
+    // order, and format, expected by the guest code running in the zkVM.
+    let remote_receipt = compose(local_receipt, remote_prover)?;
+    if let Some(artifacts) = &artifacts {
+        artifacts.write_composed_receipt(
+            &remote_receipt,
+            Some(&format!("{:?}", tx_sender.contract())),
+        )?;
+    }
+
+    // Construct function call: the seal and the verified journal are encoded as the
+    // calldata of the set function of the EvenNumber contract.
+    let calldata = set_calldata(&remote_receipt)?;
+
+    if dry_run {
+        return print_transaction(&runtime, &tx_sender, calldata, artifacts.as_ref());
+    }
+    send(&runtime, &tx_sender, calldata, eth.force)
+}
+
+/// Prints the transaction that would be sent, with its estimated gas, so that
+/// it can be handed over to a multisig or a separate relayer. The transaction
+/// is also saved to the artifacts, if any.
+///
+/// The transaction is the only output on stdout, as the JSON of a `calldata.json`
+/// artifact: `{"to": "0x…", "data": "0x…", "gas_estimate": …}`. Logs go to
+/// stderr, so that the output can be piped.
+fn print_transaction(
+    runtime: &Runtime,
+    tx_sender: &TxSender<impl TxSigner>,
+    calldata: Vec<u8>,
+    artifacts: Option<&Artifacts>,
+) -> Result<()> {
+    let gas = runtime
+        .block_on(tx_sender.estimate_gas(calldata.clone()))
+        .context("estimating gas")?;
+
+    let mut transaction = Calldata::new(Some(&format!("{:?}", tx_sender.contract())), &calldata);
+    transaction.gas_estimate =
+        Some(u64::try_from(gas).map_err(|_| anyhow!("gas estimate {gas} does not fit in a u64"))?);
+    if let Some(artifacts) = artifacts {
+        artifacts.write_calldata(&transaction)?;
+    }
+
+    println!("{}", serde_json::to_string_pretty(&transaction)?);
+    Ok(())
+}
 
+/// Sends the transaction with the given calldata, once simulated. With `force`,
+/// the transaction is sent even if it would revert.
+fn send(
+    runtime: &Runtime,
+    tx_sender: &TxSender<impl TxSigner>,
+    calldata: Vec<u8>,
+    force: bool,
+) -> Result<()> {
     // Send transaction: Finally, the TxSender component sends the transaction to the Ethereum blockchain,
     // effectively calling the set function of the EvenNumber contract with the verified number and proof.
-    runtime.block_on(tx_sender.send(calldata))?;
+    let receipt = runtime.block_on(tx_sender.simulate_and_send(calldata, force))?;
+    print_confirmation(&receipt);
 
     Ok(())
 }
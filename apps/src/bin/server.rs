@@ -0,0 +1,71 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// This application is the server-side half of the `publisher`: it composes a
+// power_modulus receipt, exported by the `client`, into an is_even Groth16
+// proof, and publishes it to your deployed app contract.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use apps::{
+    cli::{EthArgs, RemoteProverArgs},
+    print_confirmation,
+    prover::init_dev_mode,
+    proving::{compose, read_receipt},
+    set_calldata,
+};
+use clap::Parser;
+
+/// Arguments of the server CLI.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    eth: EthArgs,
+
+    #[clap(flatten)]
+    prover: RemoteProverArgs,
+
+    /// File holding the power_modulus receipt exported by the client.
+    #[clap(long, default_value = "power_modulus.receipt")]
+    receipt: PathBuf,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+    // Enable dev mode, if selected, before the runtime starts its threads.
+    init_dev_mode(&[args.prover.remote_prover]);
+
+    // Build the transaction sender first, so that a bad configuration fails before composing. The
+    // runtime keeps serving the node connections during the composition.
+    let runtime = tokio::runtime::Runtime::new()?;
+    let tx_sender = runtime.block_on(args.eth.tx_sender())?;
+    args.prover.remote_prover.check_available()?;
+
+    // Composing on Bonsai is paid for, so make sure the proof can be published.
+    runtime.block_on(tx_sender.check_health())?;
+
+    let local_receipt = read_receipt(&args.receipt)?;
+
+    // Compose the client's receipt into a Groth16 receipt of the is_even guest.
+    let remote_receipt = compose(local_receipt, args.prover.remote_prover)?;
+    let calldata = set_calldata(&remote_receipt)?;
+
+    let receipt = runtime.block_on(tx_sender.simulate_and_send(calldata, args.eth.force))?;
+    print_confirmation(&receipt);
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// This application runs the server-side stage as a long-running HTTP service:
+// clients submit their power_modulus receipts, and get back Groth16 proofs
+// ready to be published to your deployed app contract.
+
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use apps::{
+    cli::RemoteProverArgs,
+    prover::init_dev_mode,
+    service::{router, AppState},
+};
+use clap::{builder::RangedU64ValueParser, Parser};
+
+/// Arguments of the proving service.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Address to listen on.
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// Maximum number of jobs proven at the same time, at least 1.
+    #[clap(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    max_concurrent_jobs: usize,
+
+    #[clap(flatten)]
+    prover: RemoteProverArgs,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+    // Enable dev mode, if selected, before the runtime starts its threads: the
+    // jobs read the environment from the blocking thread pool.
+    init_dev_mode(&[args.prover.remote_prover]);
+
+    tokio::runtime::Runtime::new()?.block_on(serve(args))
+}
+
+async fn serve(args: Args) -> Result<()> {
+    // Check that the prover is available before accepting any job.
+    args.prover.remote_prover.check_available()?;
+    log::info!("Composing with the {} prover", args.prover.remote_prover);
+
+    let app = router(AppState::new(
+        args.prover.remote_prover,
+        args.max_concurrent_jobs,
+    ));
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    log::info!("Listening on {}", args.listen);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Discovery of the contracts deployed by `script/Deploy.s.sol`, from the
+//! `broadcast/Deploy.s.sol/<chainId>/run-latest.json` file written by
+//! `forge script --broadcast`.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+/// Default directory of the `forge script` broadcasts, relative to the project root.
+pub const DEFAULT_BROADCAST_DIR: &str = "broadcast";
+
+/// Name of the deploy script, as used in the broadcast directory.
+const DEPLOY_SCRIPT: &str = "Deploy.s.sol";
+
+#[derive(Deserialize)]
+struct Broadcast {
+    transactions: Vec<BroadcastTransaction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BroadcastTransaction {
+    transaction_type: String,
+    contract_name: Option<String>,
+    contract_address: Option<String>,
+}
+
+/// Returns the address of the contract with the given name, deployed on the
+/// given chain by the latest run of the deploy script.
+pub fn deployed_address(
+    broadcast_dir: &Path,
+    chain_id: u64,
+    contract_name: &str,
+) -> Result<String> {
+    let path = broadcast_dir
+        .join(DEPLOY_SCRIPT)
+        .join(chain_id.to_string())
+        .join("run-latest.json");
+    if !path.exists() {
+        bail!(
+            "no deployment found for chain {chain_id} at {}, deploy with `forge script script/{DEPLOY_SCRIPT} --broadcast` or set the contract address",
+            path.display()
+        );
+    }
+    let json = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let broadcast: Broadcast =
+        serde_json::from_str(&json).with_context(|| format!("parsing {}", path.display()))?;
+
+    let mut addresses: Vec<String> = broadcast
+        .transactions
+        .into_iter()
+        .filter(|tx| tx.transaction_type.starts_with("CREATE"))
+        .filter(|tx| tx.contract_name.as_deref() == Some(contract_name))
+        .filter_map(|tx| tx.contract_address)
+        .collect();
+    addresses.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    match &addresses[..] {
+        [address] => {
+            log::info!(
+                "Using {contract_name} at {address}, deployed in {}",
+                path.display()
+            );
+            Ok(address.clone())
+        }
+        [] => bail!(
+            "no {contract_name} deployment in {}, set the contract address",
+            path.display()
+        ),
+        _ => bail!(
+            "{} {contract_name} deployments in {}: {}, set the contract address to pick one",
+            addresses.len(),
+            path.display(),
+            addresses.join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::deployed_address;
+
+    fn broadcast_dir(run: &str) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let run_dir = dir.path().join("Deploy.s.sol").join("31337");
+        fs::create_dir_all(&run_dir).unwrap();
+        fs::write(run_dir.join("run-latest.json"), run).unwrap();
+        dir
+    }
+
+    fn deployment(name: &str, address: &str) -> String {
+        format!(
+            r#"{{"hash":"0x01","transactionType":"CREATE","contractName":"{name}","contractAddress":"{address}","function":null,"arguments":null}}"#
+        )
+    }
+
+    #[test]
+    fn finds_deployed_address() {
+        let run = format!(
+            r#"{{"transactions":[{},{}],"receipts":[]}}"#,
+            deployment(
+                "RiscZeroGroth16Verifier",
+                "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+            ),
+            deployment("EvenNumber", "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512")
+        );
+        let dir = broadcast_dir(&run);
+        assert_eq!(
+            deployed_address(dir.path(), 31337, "EvenNumber").unwrap(),
+            "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_or_ambiguous_deployment() {
+        let run = format!(
+            r#"{{"transactions":[{},{}],"receipts":[]}}"#,
+            deployment("EvenNumber", "0x5fbdb2315678afecb367f032d93f642f64180aa3"),
+            deployment("EvenNumber", "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512")
+        );
+        let dir = broadcast_dir(&run);
+        let err = deployed_address(dir.path(), 31337, "EvenNumber").unwrap_err();
+        assert!(err.to_string().starts_with("2 EvenNumber deployments"));
+
+        let err = deployed_address(dir.path(), 1, "EvenNumber").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("no deployment found for chain 1"));
+
+        let err = deployed_address(dir.path(), 31337, "Other").unwrap_err();
+        assert!(err.to_string().starts_with("no Other deployment"));
+    }
+}
@@ -0,0 +1,645 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Command-line argument groups shared by the applications.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use alloy_primitives::U256;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::Args;
+use composition_core::PowerModulusInput;
+use ethers::{
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder},
+    types::{Address, U256 as EthU256},
+    utils::{parse_ether, parse_units},
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    broadcast::{deployed_address, DEFAULT_BROADCAST_DIR},
+    config::{Profile, DEFAULT_CONFIG_FILE},
+    nonce::NonceStore,
+    prover::ProverBackend,
+    proving::load_or_create_salt,
+    signer::{ReadOnlyAccount, RemoteSigner, TxSigner},
+    tx_sender::GasOptions,
+    TxSender,
+};
+
+/// Derivation path of the first account of a BIP-39 mnemonic, as used by most wallets.
+const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Maximum length of the text of x, above the 78 digits of the largest U256.
+const MAX_X_LEN: usize = 128;
+
+/// Arguments selecting the chain, wallet and contract to publish to.
+///
+/// The chain ID, RPC URLs and contract address default to the values of the
+/// selected deployment profile, and the contract address to the latest
+/// deployment of the deploy script.
+#[derive(Args, Debug, Clone)]
+pub struct EthArgs {
+    /// Deployment profile of the config file, providing the chain ID, RPC URLs and contract address
+    #[clap(long)]
+    pub profile: Option<String>,
+
+    /// Deployment config file, shared with the deploy script
+    #[clap(long, default_value = DEFAULT_CONFIG_FILE)]
+    pub config: PathBuf,
+
+    /// Ethereum chain ID
+    #[clap(long, required_unless_present = "profile")]
+    pub chain_id: Option<u64>,
+
+    #[clap(flatten)]
+    pub wallet: WalletArgs,
+
+    /// Ethereum Node endpoints, in order of preference: http(s)://, ws(s):// or ipc:// URLs, or
+    /// paths of IPC sockets. Repeat the option, or separate the URLs with commas, to fail over
+    /// between several nodes.
+    #[clap(long, required_unless_present = "profile", value_delimiter = ',')]
+    pub rpc_url: Vec<String>,
+
+    /// Number of RPC endpoints that must report a transaction in the same block to confirm it.
+    /// Defaults to a majority of the healthy endpoints; set it to keep requiring several endpoints
+    /// to agree when some of them are down.
+    #[clap(long)]
+    pub quorum: Option<usize>,
+
+    /// Application's contract address on Ethereum
+    ///
+    /// Defaults to the evenNumberAddress of the profile, or else to the EvenNumber contract
+    /// deployed by the latest broadcast of the deploy script on the chain.
+    #[clap(long)]
+    pub contract: Option<String>,
+
+    /// Directory of the `forge script` broadcasts, to find the deployed contract in
+    #[clap(long, default_value = DEFAULT_BROADCAST_DIR)]
+    pub broadcast_dir: PathBuf,
+
+    /// Send the transaction even if simulating it shows that it would revert.
+    ///
+    /// As its gas cannot be estimated, the gas limit defaults to the latest block gas limit, capped by
+    /// --max-cost.
+    #[clap(long)]
+    pub force: bool,
+
+    /// Number of confirmations to wait for, including the block that includes
+    /// the transaction, before it is considered final.
+    #[clap(long, default_value_t = 1)]
+    pub confirmations: u64,
+
+    /// Seconds to wait for the transaction to be confirmed before failing. Set to 0 to wait
+    /// forever.
+    #[clap(long, default_value_t = 1800)]
+    pub confirmation_timeout: u64,
+
+    /// File tracking the last transaction sent from each account, to pick the
+    /// nonce of the next one, and replace or cancel it.
+    #[clap(long, default_value = "nonces.json")]
+    pub nonce_file: PathBuf,
+
+    #[clap(flatten)]
+    pub gas: GasArgs,
+}
+
+impl EthArgs {
+    /// Creates a new transaction sender using the parsed arguments.
+    ///
+    /// WebSocket and IPC connections are served by the current tokio runtime,
+    /// so the sender must not outlive it.
+    pub async fn tx_sender(&self) -> Result<TxSender<Box<dyn TxSigner>>> {
+        self.tx_sender_with(self.wallet.signer()?, None).await
+    }
+
+    /// Creates a transaction sender for cancellations, which are transfers to
+    /// the wallet itself and so need no contract address.
+    pub async fn cancel_tx_sender(&self) -> Result<TxSender<Box<dyn TxSigner>>> {
+        let signer = self.wallet.signer()?;
+        let address = format!("{:?}", signer.address());
+        self.tx_sender_with(signer, Some(address)).await
+    }
+
+    /// Creates a transaction sender that only estimates and simulates
+    /// transactions, from the `--from` address if set, so that dry runs do not
+    /// need wallet credentials.
+    pub async fn dry_run_tx_sender(&self) -> Result<TxSender<Box<dyn TxSigner>>> {
+        let signer: Box<dyn TxSigner> = match self.wallet.from {
+            Some(address) => Box::new(ReadOnlyAccount(address)),
+            None => Box::new(ReadOnlyAccount(self.wallet.signer()?.address())),
+        };
+        self.tx_sender_with(signer, None).await
+    }
+
+    /// Creates a transaction sender to the given contract, or else to the
+    /// contract address of the arguments.
+    async fn tx_sender_with(
+        &self,
+        signer: Box<dyn TxSigner>,
+        contract: Option<String>,
+    ) -> Result<TxSender<Box<dyn TxSigner>>> {
+        let profile = self.load_profile()?;
+        let chain_id = self.chain_id(profile.as_ref())?;
+        let contract = match contract {
+            Some(contract) => contract,
+            None => self.contract_address(chain_id, profile.as_ref())?,
+        };
+        let mut tx_sender = TxSender::new(
+            chain_id,
+            &self.rpc_urls(profile.as_ref())?,
+            signer,
+            &contract,
+        )
+        .await?;
+        if let Some(quorum) = self.quorum {
+            tx_sender = tx_sender.with_quorum(quorum)?;
+        }
+        if self.confirmation_timeout > 0 {
+            tx_sender =
+                tx_sender.with_confirmation_timeout(Duration::from_secs(self.confirmation_timeout));
+        }
+        Ok(tx_sender
+            .with_gas_options(self.gas.options())
+            .with_confirmations(self.confirmations)
+            .with_nonce_store(NonceStore::new(&self.nonce_file)))
+    }
+
+    /// Loads the selected deployment profile, if any.
+    fn load_profile(&self) -> Result<Option<Profile>> {
+        self.profile
+            .as_deref()
+            .map(|name| Profile::load(&self.config, name))
+            .transpose()
+    }
+
+    fn chain_id(&self, profile: Option<&Profile>) -> Result<u64> {
+        let profile_chain_id = profile.map(|profile| profile.chain_id);
+        if let (Some(chain_id), Some(profile_chain_id)) = (self.chain_id, profile_chain_id) {
+            ensure!(
+                chain_id == profile_chain_id,
+                "--chain-id {chain_id} does not match the chain ID {profile_chain_id} of the profile"
+            );
+        }
+        self.chain_id
+            .or(profile_chain_id)
+            .context("no chain ID, set --chain-id or select a profile")
+    }
+
+    fn rpc_urls(&self, profile: Option<&Profile>) -> Result<Vec<String>> {
+        match profile {
+            _ if !self.rpc_url.is_empty() => Ok(self.rpc_url.clone()),
+            Some(profile) if !profile.rpc_url.is_empty() => profile.rpc_urls(),
+            _ => bail!("no RPC URL, set --rpc-url or rpcUrl in the profile"),
+        }
+    }
+
+    fn contract_address(&self, chain_id: u64, profile: Option<&Profile>) -> Result<String> {
+        match self
+            .contract
+            .clone()
+            .or_else(|| profile.and_then(|profile| profile.even_number_address.clone()))
+        {
+            Some(contract) => Ok(contract),
+            // The error tells why no deployment was found, and how to set the address.
+            None => deployed_address(&self.broadcast_dir, chain_id, "EvenNumber"),
+        }
+    }
+}
+
+/// Arguments selecting the wallet signing the transactions: a remote signing
+/// service, an encrypted JSON keystore, a BIP-39 mnemonic, or a raw private key.
+#[derive(Args, Debug, Clone)]
+pub struct WalletArgs {
+    /// Hex-encoded private key of the wallet, when no other wallet is set
+    #[clap(long, env, required_unless_present_any = ["remote_signer", "keystore", "mnemonic_file", "from"])]
+    pub eth_wallet_private_key: Option<String>,
+
+    /// JSON-RPC endpoint of a signing service, signing transactions with eth_signTransaction
+    #[clap(long, requires = "signer_address", conflicts_with_all = ["keystore", "mnemonic_file"])]
+    pub remote_signer: Option<String>,
+
+    /// Address of the account signing transactions on the remote signer
+    #[clap(long, requires = "remote_signer")]
+    pub signer_address: Option<Address>,
+
+    /// Encrypted JSON keystore file of the wallet
+    #[clap(long, conflicts_with = "mnemonic_file")]
+    pub keystore: Option<PathBuf>,
+
+    /// File holding the password of the keystore
+    ///
+    /// When not set, the password is prompted for without echo.
+    #[clap(long, requires = "keystore")]
+    pub keystore_password_file: Option<PathBuf>,
+
+    /// File holding the BIP-39 mnemonic of the wallet
+    #[clap(long)]
+    pub mnemonic_file: Option<PathBuf>,
+
+    /// BIP-32 derivation path of the wallet's key from the mnemonic
+    #[clap(long, requires = "mnemonic_file", default_value = DEFAULT_DERIVATION_PATH)]
+    pub derivation_path: String,
+
+    /// Address to estimate a dry run from, without any wallet credentials. Defaults to the address
+    /// of the wallet.
+    #[clap(long)]
+    pub from: Option<Address>,
+}
+
+impl WalletArgs {
+    /// Returns the signer of the selected wallet.
+    pub fn signer(&self) -> Result<Box<dyn TxSigner>> {
+        match (&self.remote_signer, self.signer_address) {
+            (Some(url), Some(address)) => Ok(Box::new(RemoteSigner::new(url, address)?)),
+            _ => Ok(Box::new(self.wallet()?)),
+        }
+    }
+
+    /// Loads the selected local wallet. Passwords and mnemonics are held in
+    /// zeroizing buffers, and are never included in errors or logs.
+    pub fn wallet(&self) -> Result<LocalWallet> {
+        if let Some(keystore) = &self.keystore {
+            let password = match &self.keystore_password_file {
+                Some(path) => read_secret(path, "keystore password")?,
+                None => Zeroizing::new(
+                    rpassword::prompt_password("Keystore password: ")
+                        .context("prompting for the keystore password")?,
+                ),
+            };
+            LocalWallet::decrypt_keystore(keystore, password.trim_end_matches(['\r', '\n']))
+                .with_context(|| format!("decrypting keystore {}", keystore.display()))
+        } else if let Some(path) = &self.mnemonic_file {
+            let mnemonic = read_secret(path, "mnemonic")?;
+            MnemonicBuilder::<English>::default()
+                .phrase(mnemonic.trim())
+                .derivation_path(&self.derivation_path)
+                .context("invalid derivation path")?
+                .build()
+                .map_err(|_| anyhow!("invalid mnemonic in {}", path.display()))
+        } else {
+            let private_key = self
+                .eth_wallet_private_key
+                .as_deref()
+                .context("no wallet selected")?;
+            private_key
+                .parse()
+                .map_err(|_| anyhow!("invalid wallet private key"))
+        }
+    }
+}
+
+/// Reads a secret from a file into a zeroizing buffer.
+fn read_secret(path: &Path, what: &str) -> Result<Zeroizing<String>> {
+    fs::read_to_string(path)
+        .map(Zeroizing::new)
+        .with_context(|| format!("reading {what} from {}", path.display()))
+}
+
+/// Arguments setting the fees and gas limit of the EIP-1559 transaction sent.
+#[derive(Args, Debug, Clone)]
+pub struct GasArgs {
+    /// Maximum fee per gas, in gwei. Estimated from the node if not set.
+    #[clap(long, value_parser = parse_gwei)]
+    pub max_fee_per_gas: Option<EthU256>,
+
+    /// Maximum priority fee per gas, in gwei. Estimated from the node if not set.
+    #[clap(long, value_parser = parse_gwei)]
+    pub max_priority_fee: Option<EthU256>,
+
+    /// Gas limit of the transaction. Estimated from the node if not set.
+    #[clap(long)]
+    pub gas_limit: Option<u64>,
+
+    /// Percentage added to the estimated gas to get the gas limit.
+    #[clap(long, default_value_t = 20)]
+    pub gas_margin: u64,
+
+    /// Maximum cost of the transaction, in ETH, computed as its gas limit times
+    /// its maximum fee per gas. The transaction is not sent if it may cost more.
+    #[clap(long, value_parser = parse_eth)]
+    pub max_cost: Option<EthU256>,
+
+    /// Seconds after which a transaction that is not included is replaced with
+    /// higher fees. Set to 0 to never replace transactions.
+    #[clap(long, default_value_t = 180)]
+    pub replace_after: u64,
+
+    /// Percentage by which the fees of a replacement transaction are bumped, at least 10.
+    #[clap(long, default_value_t = 20)]
+    pub fee_bump: u64,
+}
+
+impl GasArgs {
+    /// Returns the gas options of the transaction sender.
+    pub fn options(&self) -> GasOptions {
+        GasOptions {
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee,
+            gas_limit: self.gas_limit.map(EthU256::from),
+            gas_margin_percent: self.gas_margin,
+            max_cost: self.max_cost,
+            replace_after: (self.replace_after > 0)
+                .then(|| Duration::from_secs(self.replace_after)),
+            fee_bump_percent: self.fee_bump,
+        }
+    }
+}
+
+fn parse_gwei(value: &str) -> Result<EthU256> {
+    Ok(parse_units(value, "gwei")?.into())
+}
+
+fn parse_eth(value: &str) -> Result<EthU256> {
+    Ok(parse_ether(value)?)
+}
+
+/// Arguments of the power_modulus guest input, proven locally.
+///
+/// All values are 256-bit integers: larger moduli, such as 2048-bit RSA ones,
+/// are not supported.
+#[derive(Args, Debug, Clone)]
+pub struct InputArgs {
+    /// Public modulus of the LOCAL guest input, up to 256 bits, as hex (0x-prefixed) or decimal
+    #[clap(short, long)]
+    pub n: U256,
+    /// Public exponent of the LOCAL guest input, up to 256 bits, as hex (0x-prefixed) or decimal
+    #[clap(short, long)]
+    pub e: U256,
+
+    /// File holding the private value of the LOCAL guest input, below n, as hex (0x-prefixed) or decimal
+    ///
+    /// When neither --x-file nor --x-stdin is set, the value is prompted for without echo.
+    #[clap(long, conflicts_with = "x_stdin")]
+    pub x_file: Option<PathBuf>,
+    /// Read the private value of the LOCAL guest input from the first line of stdin
+    #[clap(long)]
+    pub x_stdin: bool,
+
+    /// File holding the hex-encoded salt of the commitment to x, created with a random salt if missing.
+    ///
+    /// When set, the LOCAL proof also commits to sha256(x || salt), so that separate proofs about the
+    /// same x can be linked without revealing it.
+    #[clap(long)]
+    pub salt_file: Option<PathBuf>,
+}
+
+impl InputArgs {
+    /// Returns the power_modulus input, reading the private value, and loading
+    /// or creating the salt if needed.
+    pub fn input(&self) -> Result<PowerModulusInput> {
+        // x is parsed into the input itself, which zeroes it when dropped.
+        let mut input = PowerModulusInput {
+            n: self.n,
+            e: self.e,
+            x: U256::ZERO,
+            salt: None,
+        };
+        self.read_x(&mut input.x)?;
+        input.salt = self
+            .salt_file
+            .as_deref()
+            .map(load_or_create_salt)
+            .transpose()?;
+        Ok(input)
+    }
+
+    /// Reads the private value from the selected source.
+    ///
+    /// The text is read into a fixed zeroizing buffer, and is never included in
+    /// errors or logs.
+    fn read_x(&self, x: &mut U256) -> Result<()> {
+        let mut buf = Zeroizing::new([0u8; MAX_X_LEN]);
+        let len = if let Some(path) = &self.x_file {
+            File::open(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut file| read_line(&mut file, &mut buf[..]))
+                .with_context(|| format!("reading x from {}", path.display()))?
+        } else if self.x_stdin {
+            read_line(&mut io::stdin(), &mut buf[..]).context("reading x from stdin")?
+        } else {
+            prompt_x(&mut buf[..]).context("prompting for x")?
+        };
+
+        *x = std::str::from_utf8(&buf[..len])
+            .ok()
+            .and_then(|text| text.trim().parse().ok())
+            .ok_or_else(|| anyhow!("x must be a hex (0x-prefixed) or decimal integer"))?;
+        Ok(())
+    }
+}
+
+/// Reads the first line of the reader into the buffer, without its newline,
+/// and returns its length. The line is read byte by byte, so that it is not
+/// copied into any other buffer of ours.
+fn read_line(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    loop {
+        ensure!(len < buf.len(), "longer than {} characters", buf.len());
+        match reader.read(&mut buf[len..=len]) {
+            Ok(0) => return Ok(len),
+            Ok(_) if buf[len] == b'\n' => {
+                buf[len] = 0;
+                return Ok(len);
+            }
+            Ok(_) => len += 1,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Prompts for x on the terminal without echo, reading it into the buffer.
+#[cfg(unix)]
+fn prompt_x(buf: &mut [u8]) -> Result<usize> {
+    let mut tty = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")?;
+    tty.write_all(b"x: ")?;
+    let len = {
+        let _hidden = HiddenInput::new(&tty)?;
+        read_line(&mut &tty, buf)
+    };
+    tty.write_all(b"\n")?;
+    len
+}
+
+/// Prompts for x on the terminal without echo, copying it into the buffer.
+#[cfg(not(unix))]
+fn prompt_x(buf: &mut [u8]) -> Result<usize> {
+    let text = Zeroizing::new(rpassword::prompt_password("x: ")?);
+    let line = text.as_bytes();
+    ensure!(
+        line.len() <= buf.len(),
+        "longer than {} characters",
+        buf.len()
+    );
+    buf[..line.len()].copy_from_slice(line);
+    Ok(line.len())
+}
+
+/// Disables the echo of a terminal, until dropped.
+#[cfg(unix)]
+struct HiddenInput<'a> {
+    tty: &'a File,
+    termios: libc::termios,
+}
+
+#[cfg(unix)]
+impl<'a> HiddenInput<'a> {
+    fn new(tty: &'a File) -> Result<Self> {
+        let fd = std::os::fd::AsRawFd::as_raw_fd(tty);
+        // SAFETY: termios is a plain C struct, which tcgetattr fills in.
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+        // SAFETY: fd is an open terminal, and termios a valid pointer.
+        if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+            return Err(io::Error::last_os_error()).context("reading the terminal attributes");
+        }
+        let mut hidden = termios;
+        hidden.c_lflag &= !libc::ECHO;
+        // SAFETY: As above.
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &hidden) } != 0 {
+            return Err(io::Error::last_os_error()).context("disabling the terminal echo");
+        }
+        Ok(Self { tty, termios })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for HiddenInput<'_> {
+    fn drop(&mut self) {
+        let fd = std::os::fd::AsRawFd::as_raw_fd(self.tty);
+        // SAFETY: The borrowed terminal is still open.
+        unsafe { libc::tcsetattr(fd, libc::TCSANOW, &self.termios) };
+    }
+}
+
+/// Arguments selecting the prover of the LOCAL power_modulus stage.
+#[derive(Args, Debug, Clone)]
+pub struct LocalProverArgs {
+    /// Prover backend of the LOCAL power_modulus stage, which receives the private input
+    #[clap(long, value_enum, default_value_t = ProverBackend::Local)]
+    pub local_prover: ProverBackend,
+}
+
+/// Arguments selecting the prover of the REMOTE is_even stage.
+#[derive(Args, Debug, Clone)]
+pub struct RemoteProverArgs {
+    /// Prover backend of the REMOTE is_even stage, which composes the power_modulus receipt
+    #[clap(long, value_enum, default_value_t = ProverBackend::Bonsai)]
+    pub remote_prover: ProverBackend,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        eth: EthArgs,
+    }
+
+    fn eth_args(args: &[&str]) -> EthArgs {
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        let mut argv = vec!["cli", "--profile", "test", "--eth-wallet-private-key", key];
+        argv.extend_from_slice(args);
+        Cli::parse_from(argv).eth
+    }
+
+    fn profile(rpc_url: &str) -> Profile {
+        Profile {
+            chain_id: 11155111,
+            risc_zero_verifier_address: None,
+            rpc_url: vec![rpc_url.to_string()],
+            even_number_address: None,
+        }
+    }
+
+    #[test]
+    fn rpc_url_overrides_profile() {
+        // The profile URLs, and their env vars, are not used when --rpc-url is set.
+        let profile = profile("https://${CLI_TEST_UNSET_API_KEY}.example.com");
+        let args = eth_args(&["--rpc-url", "http://localhost:8545,ws://localhost:8546"]);
+        assert_eq!(
+            args.rpc_urls(Some(&profile)).unwrap(),
+            ["http://localhost:8545", "ws://localhost:8546"]
+        );
+
+        let err = eth_args(&[]).rpc_urls(Some(&profile)).unwrap_err();
+        assert_eq!(err.to_string(), "env var CLI_TEST_UNSET_API_KEY is not set");
+    }
+
+    #[test]
+    fn requires_rpc_url() {
+        let mut profile = profile("http://localhost:8545");
+        assert_eq!(
+            eth_args(&[]).rpc_urls(Some(&profile)).unwrap(),
+            ["http://localhost:8545"]
+        );
+
+        profile.rpc_url.clear();
+        let err = eth_args(&[]).rpc_urls(Some(&profile)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no RPC URL, set --rpc-url or rpcUrl in the profile"
+        );
+    }
+
+    #[test]
+    fn reads_first_line_of_x() {
+        let mut buf = [0u8; 8];
+        let len = read_line(&mut &b"0x1234\r\n5678"[..], &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"0x1234\r");
+        let len = read_line(&mut &b"42"[..], &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"42");
+
+        let err = read_line(&mut &b"123456789"[..], &mut buf).unwrap_err();
+        assert_eq!(err.to_string(), "longer than 8 characters");
+    }
+
+    #[tokio::test]
+    async fn cancels_without_contract() {
+        // No profile, --contract nor deployment to find the contract address in.
+        let broadcast_dir = tempfile::tempdir().unwrap();
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        let args = Cli::parse_from([
+            "cli",
+            "--chain-id",
+            "31337",
+            "--rpc-url",
+            "http://localhost:8545",
+            "--eth-wallet-private-key",
+            key,
+            "--broadcast-dir",
+            broadcast_dir.path().to_str().unwrap(),
+        ])
+        .eth;
+        assert!(args.tx_sender().await.is_err());
+
+        let tx_sender = args.cancel_tx_sender().await.unwrap();
+        assert_eq!(
+            tx_sender.contract(),
+            args.wallet.signer().unwrap().address()
+        );
+    }
+}
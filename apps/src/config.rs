@@ -0,0 +1,186 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deployment profiles of `script/config.toml`, shared by `script/Deploy.s.sol`
+//! and the publisher.
+//!
+//! Each `[profile.<name>]` table holds the `chainId` and
+//! `riscZeroVerifierAddress` read by the deploy script, and optionally the
+//! `rpcUrl` and `evenNumberAddress` read by the publisher. `${VAR}`
+//! references in `rpcUrl` are replaced by the value of the `VAR` environment
+//! variable when the URLs are used, so that API keys are not committed.
+
+use std::{collections::BTreeMap, env, fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+/// Default path of the deployment config file, relative to the project root.
+pub const DEFAULT_CONFIG_FILE: &str = "script/config.toml";
+
+/// Deployment profile of the config file.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// Chain ID of the network.
+    pub chain_id: u64,
+    /// Address of the RISC Zero verifier contract, if already deployed.
+    pub risc_zero_verifier_address: Option<String>,
+    /// Node endpoints of the network, in order of preference.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub rpc_url: Vec<String>,
+    /// Address of the deployed `EvenNumber` contract.
+    pub even_number_address: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    profile: BTreeMap<String, Profile>,
+}
+
+impl Profile {
+    /// Loads the profile with the given name from the config file.
+    pub fn load(path: &Path, name: &str) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("reading config file {}", path.display()))?;
+        Self::parse(&text, name).with_context(|| format!("in config file {}", path.display()))
+    }
+
+    fn parse(text: &str, name: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(text)?;
+        let Some(profile) = config.profile.remove(name) else {
+            let names: Vec<_> = config.profile.keys().map(String::as_str).collect();
+            bail!(
+                "no profile {name}, available profiles: {}",
+                names.join(", ")
+            );
+        };
+        Ok(profile)
+    }
+
+    /// Returns the RPC URLs of the profile, with their `${VAR}` references
+    /// replaced by the values of the environment variables.
+    pub fn rpc_urls(&self) -> Result<Vec<String>> {
+        self.rpc_url
+            .iter()
+            .map(|url| expand_env(url, |var| env::var(var).ok()))
+            .collect()
+    }
+}
+
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(url) => vec![url],
+        OneOrMany::Many(urls) => urls,
+    })
+}
+
+/// Replaces the `${VAR}` references of the given text by the values of the
+/// variables, read with `var`.
+fn expand_env(text: &str, var: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unterminated ${{ in {text}"))?;
+        let name = &rest[start + 2..start + end];
+        let value = var(name).with_context(|| format!("env var {name} is not set"))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_env, Profile};
+
+    const CONFIG: &str = r#"
+        [profile.mainnet]
+        chainId = 1
+        riscZeroVerifierAddress = "0x8EaB2D97Dfce405A1692a21b3ff3A172d593D319"
+
+        [profile.sepolia]
+        chainId = 11155111
+        riscZeroVerifierAddress = "0x925d8331ddc0a1F0d96E68CF073DFE1d92b69187"
+        rpcUrl = ["https://${PROFILE_TEST_HOST}/rpc", "ws://localhost:8546"]
+        evenNumberAddress = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+    "#;
+
+    #[test]
+    fn parses_profiles() {
+        let profile = Profile::parse(CONFIG, "sepolia").unwrap();
+        assert_eq!(
+            profile,
+            Profile {
+                chain_id: 11155111,
+                risc_zero_verifier_address: Some(
+                    "0x925d8331ddc0a1F0d96E68CF073DFE1d92b69187".to_string()
+                ),
+                rpc_url: vec![
+                    "https://${PROFILE_TEST_HOST}/rpc".to_string(),
+                    "ws://localhost:8546".to_string()
+                ],
+                even_number_address: Some("0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string()),
+            }
+        );
+
+        let profile = Profile::parse(CONFIG, "mainnet").unwrap();
+        assert_eq!(profile.chain_id, 1);
+        assert!(profile.rpc_url.is_empty());
+        assert_eq!(profile.even_number_address, None);
+    }
+
+    #[test]
+    fn expands_env_vars() {
+        let var = |name: &str| (name == "HOST").then(|| "sepolia.example.com".to_string());
+        assert_eq!(
+            expand_env("https://${HOST}/rpc/${HOST}", var).unwrap(),
+            "https://sepolia.example.com/rpc/sepolia.example.com"
+        );
+        assert_eq!(
+            expand_env("ws://localhost:8546", var).unwrap(),
+            "ws://localhost:8546"
+        );
+        assert_eq!(
+            expand_env("https://${API_KEY}.example.com", var)
+                .unwrap_err()
+                .to_string(),
+            "env var API_KEY is not set"
+        );
+        assert_eq!(
+            expand_env("https://${HOST", var).unwrap_err().to_string(),
+            "unterminated ${ in https://${HOST"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_profile() {
+        let err = Profile::parse(CONFIG, "holesky").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no profile holesky, available profiles: mainnet, sepolia"
+        );
+    }
+}
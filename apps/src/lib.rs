@@ -0,0 +1,97 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared building blocks of the `client`, `server` and `publisher` applications.
+//!
+//! The power_modulus proof over the private input is produced on the client,
+//! with [proving::prove_power_modulus]. Its receipt is then handed to the
+//! server, which composes it into an is_even Groth16 proof with
+//! [proving::compose], and publishes it with a [TxSender].
+
+pub mod artifacts;
+pub mod broadcast;
+pub mod cli;
+pub mod config;
+#[cfg(test)]
+mod mock_node;
+pub mod nonce;
+pub mod privacy;
+pub mod prover;
+pub mod proving;
+pub mod revert;
+pub mod rpc;
+pub mod service;
+pub mod signer;
+pub mod tx_sender;
+
+use alloy_sol_types::{sol, SolInterface};
+use anyhow::{Context, Result};
+use composition_core::IsEvenJournal;
+use ethers::types::TransactionReceipt;
+use risc0_ethereum_contracts::groth16;
+use risc0_zkvm::Receipt;
+
+pub use tx_sender::TxSender;
+
+// `IEvenNumber` interface automatically generated via the alloy `sol!` macro.
+sol! {
+    interface IEvenNumber {
+        function set(uint256 n, uint256 e, uint256 y, bytes32 commitment, bytes calldata seal);
+    }
+}
+
+/// Encodes the seal of the given Groth16 receipt, prefixed with the selector
+/// expected by the RISC Zero verifier contract.
+pub fn encode_seal(receipt: &Receipt) -> Result<Vec<u8>> {
+    groth16::encode(receipt.inner.groth16()?.seal.clone())
+}
+
+/// Builds the calldata of `IEvenNumber.set`, publishing the given is_even
+/// Groth16 receipt.
+pub fn set_calldata(receipt: &Receipt) -> Result<Vec<u8>> {
+    // Encode the seal with the selector.
+    let seal = encode_seal(receipt)?;
+
+    // Decode Journal: Upon receiving the proof, the application decodes the journal to extract
+    // the verified `(n, e, x^e mod n)` and commitment to `x`. This ensures that the values being submitted to the blockchain
+    // match the values that were verified off-chain.
+    let IsEvenJournal {
+        n,
+        e,
+        y,
+        commitment,
+    } = IsEvenJournal::decode(&receipt.journal.bytes).context("decoding journal data")?;
+
+    // Construct function call: Using the IEvenNumber interface, the application constructs
+    // the ABI-encoded function call for the set function of the EvenNumber contract.
+    // This call includes the verified values, and the seal (proof).
+    Ok(IEvenNumber::IEvenNumberCalls::set(IEvenNumber::setCall {
+        n,
+        e,
+        y,
+        commitment,
+        seal: seal.into(),
+    })
+    .abi_encode())
+}
+
+/// Prints the final block of a confirmed transaction.
+pub fn print_confirmation(receipt: &TransactionReceipt) {
+    println!(
+        "Transaction {:?} confirmed in block {} ({:?})",
+        receipt.transaction_hash,
+        receipt.block_number.unwrap_or_default(),
+        receipt.block_hash.unwrap_or_default()
+    );
+}
@@ -0,0 +1,134 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mock JSON-RPC node of the tests, served over HTTP or IPC.
+
+use std::{net::SocketAddr, path::Path, sync::Arc};
+
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{unix::OwnedWriteHalf, UnixListener, UnixStream},
+    sync::{broadcast, Mutex},
+};
+
+/// Answers a JSON-RPC method call, given its method and params, with its
+/// result or with a JSON-RPC error object.
+pub(crate) type Handler = Arc<dyn Fn(&str, &Value) -> Result<Value, Value> + Send + Sync>;
+
+/// Returns the error object of an unsupported method.
+pub(crate) fn method_not_found() -> Value {
+    json!({ "code": -32601, "message": "method not found" })
+}
+
+/// Serves the handler over HTTP, on a local port.
+pub(crate) async fn serve_http(handler: Handler) -> SocketAddr {
+    let app = Router::new().route(
+        "/",
+        post(move |Json(request): Json<Value>| {
+            let response = answer(&handler, &request);
+            async move { Json(response) }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+/// Serves the handler over an IPC socket at the given path. `newHeads`
+/// subscriptions are served with the blocks sent to `heads`.
+pub(crate) fn serve_ipc(path: &Path, handler: Handler, heads: broadcast::Sender<Value>) {
+    let listener = UnixListener::bind(path).unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve_ipc_connection(stream, handler.clone(), heads.clone()));
+        }
+    });
+}
+
+async fn serve_ipc_connection(
+    stream: UnixStream,
+    handler: Handler,
+    heads: broadcast::Sender<Value>,
+) {
+    let (mut reader, writer) = stream.into_split();
+    let writer = Arc::new(Mutex::new(writer));
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        match reader.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+
+        // Requests are concatenated JSON values, possibly split across reads.
+        let mut requests = serde_json::Deserializer::from_slice(&buf).into_iter::<Value>();
+        let mut complete = Vec::new();
+        for request in requests.by_ref() {
+            match request {
+                Ok(request) => complete.push(request),
+                Err(_) => break,
+            }
+        }
+        let offset = requests.byte_offset();
+        buf.drain(..offset);
+
+        for request in complete {
+            let response = if request["method"] == "eth_subscribe" {
+                let mut blocks = heads.subscribe();
+                let writer = writer.clone();
+                tokio::spawn(async move {
+                    while let Ok(block) = blocks.recv().await {
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": "eth_subscription",
+                            "params": { "subscription": "0x1", "result": block },
+                        });
+                        if write(&writer, &notification).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+                json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x1" })
+            } else {
+                answer(&handler, &request)
+            };
+            if write(&writer, &response).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn write(writer: &Mutex<OwnedWriteHalf>, value: &Value) -> std::io::Result<()> {
+    writer
+        .lock()
+        .await
+        .write_all(&serde_json::to_vec(value).unwrap())
+        .await
+}
+
+fn answer(handler: &Handler, request: &Value) -> Value {
+    let id = &request["id"];
+    match handler(
+        request["method"].as_str().unwrap_or_default(),
+        &request["params"],
+    ) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
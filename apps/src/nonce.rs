@@ -0,0 +1,115 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local tracking of the transactions sent from each account.
+//!
+//! The last transaction sent from each account, on each chain, is recorded in
+//! a JSON file, so that the next run does not reuse the nonce of a transaction
+//! still pending in the mempool, and so that a stuck transaction can be
+//! replaced or cancelled with higher fees.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use ethers::types::{Address, TxHash, U256};
+use serde::{Deserialize, Serialize};
+
+/// Transaction last sent from an account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SentTransaction {
+    /// Nonce of the transaction.
+    pub nonce: U256,
+    /// Hash of the transaction.
+    pub hash: TxHash,
+    /// Maximum fee per gas of the transaction, in wei.
+    pub max_fee_per_gas: U256,
+    /// Maximum priority fee per gas of the transaction, in wei.
+    pub max_priority_fee_per_gas: U256,
+}
+
+/// JSON file recording the last transaction sent from each account.
+#[derive(Clone, Debug)]
+pub struct NonceStore {
+    path: PathBuf,
+}
+
+impl NonceStore {
+    /// Opens the store at the given path. The file is created on the first
+    /// recorded transaction.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the last transaction sent from the given account.
+    pub fn last(&self, chain_id: u64, account: Address) -> Result<Option<SentTransaction>> {
+        Ok(self.read()?.remove(&key(chain_id, account)))
+    }
+
+    /// Records a transaction sent from the given account.
+    pub fn record(&self, chain_id: u64, account: Address, tx: SentTransaction) -> Result<()> {
+        let mut transactions = self.read()?;
+        transactions.insert(key(chain_id, account), tx);
+        write(&self.path, &transactions)
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, SentTransaction>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let json = fs::read_to_string(&self.path)
+            .with_context(|| format!("reading {}", self.path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("parsing {}", self.path.display()))
+    }
+}
+
+fn key(chain_id: u64, account: Address) -> String {
+    format!("{chain_id}:{account:?}")
+}
+
+fn write(path: &Path, transactions: &BTreeMap<String, SentTransaction>) -> Result<()> {
+    let json = serde_json::to_string_pretty(transactions)?;
+    fs::write(path, json).with_context(|| format!("writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, TxHash, U256};
+
+    use super::{NonceStore, SentTransaction};
+
+    #[test]
+    fn records_last_transaction_per_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = NonceStore::new(dir.path().join("nonces.json"));
+        let account = Address::repeat_byte(1);
+
+        assert_eq!(store.last(1, account).unwrap(), None);
+
+        let tx = SentTransaction {
+            nonce: U256::from(7),
+            hash: TxHash::repeat_byte(2),
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(1_000_000_000u64),
+        };
+        store.record(1, account, tx.clone()).unwrap();
+
+        assert_eq!(store.last(1, account).unwrap(), Some(tx));
+        assert_eq!(store.last(11155111, account).unwrap(), None);
+        assert_eq!(store.last(1, Address::repeat_byte(3)).unwrap(), None);
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Privacy policy of the proving stages.
+//!
+//! Every `ExecutorEnv` is wrapped in a [StageEnv] tagged with the privacy of
+//! the inputs written into it. A private stage is only ever proven by a
+//! `LocalProver`, in this process, so that a misconfigured backend cannot
+//! send its inputs off this machine.
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use risc0_zkvm::{ExecutorEnv, ProverOpts, Receipt, VerifierContext};
+
+use crate::prover::ProverBackend;
+
+/// Privacy of the inputs of a stage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Privacy {
+    /// The inputs must never leave this machine.
+    Private,
+    /// The inputs may be sent to any prover.
+    Public,
+}
+
+impl fmt::Display for Privacy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Private => write!(f, "private"),
+            Self::Public => write!(f, "public"),
+        }
+    }
+}
+
+impl Privacy {
+    /// Checks that a stage with this privacy may be proven by the given backend.
+    ///
+    /// Private stages may only be proven by the backends using a `LocalProver`.
+    pub fn check(self, backend: ProverBackend) -> Result<()> {
+        match (self, backend) {
+            (Self::Public, _) | (Self::Private, ProverBackend::Local | ProverBackend::DevMode) => {
+                Ok(())
+            }
+            (Self::Private, _) => bail!(
+                "refusing to send private inputs to the {backend} prover: \
+                 private stages may only use the local or dev-mode provers"
+            ),
+        }
+    }
+}
+
+/// `ExecutorEnv` of a stage, tagged with the privacy of its inputs.
+pub struct StageEnv<'a> {
+    name: &'static str,
+    privacy: Privacy,
+    env: ExecutorEnv<'a>,
+}
+
+impl<'a> StageEnv<'a> {
+    /// Tags the env of the named stage as holding private inputs.
+    pub fn private(name: &'static str, env: ExecutorEnv<'a>) -> Self {
+        Self {
+            name,
+            privacy: Privacy::Private,
+            env,
+        }
+    }
+
+    /// Tags the env of the named stage as holding public inputs.
+    pub fn public(name: &'static str, env: ExecutorEnv<'a>) -> Self {
+        Self {
+            name,
+            privacy: Privacy::Public,
+            env,
+        }
+    }
+
+    /// Proves the stage with the given backend, once the privacy policy allows it.
+    pub fn prove(self, backend: ProverBackend, elf: &[u8], opts: &ProverOpts) -> Result<Receipt> {
+        // Check the policy before the prover is even created.
+        self.privacy.check(backend)?;
+        let prover = backend.prover()?;
+
+        log::info!(
+            "Proving {} stage {} with the {} prover",
+            self.privacy,
+            self.name,
+            prover.get_name()
+        );
+        Ok(prover
+            .prove_with_ctx(self.env, &VerifierContext::default(), elf, opts)?
+            .receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+    use risc0_zkvm::{ExecutorEnv, ProverOpts};
+
+    use super::{Privacy, StageEnv};
+    use crate::prover::ProverBackend;
+
+    #[test]
+    fn private_stage_only_uses_local_prover() {
+        for backend in ProverBackend::value_variants() {
+            let allowed = Privacy::Private.check(*backend).is_ok();
+            assert_eq!(
+                allowed,
+                matches!(backend, ProverBackend::Local | ProverBackend::DevMode),
+                "{backend}"
+            );
+        }
+    }
+
+    #[test]
+    fn public_stage_uses_any_prover() {
+        for backend in ProverBackend::value_variants() {
+            assert!(Privacy::Public.check(*backend).is_ok(), "{backend}");
+        }
+    }
+
+    #[test]
+    fn refuses_to_prove_private_stage_remotely() {
+        for backend in [ProverBackend::Bonsai, ProverBackend::External] {
+            let env = ExecutorEnv::builder().build().unwrap();
+            let err = StageEnv::private("test", env)
+                .prove(backend, &[], &ProverOpts::default())
+                .unwrap_err();
+            assert!(
+                err.to_string()
+                    .starts_with("refusing to send private inputs"),
+                "{err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Explicit selection of the prover backend of each stage, so that operators
+//! know exactly where the inputs of a stage are sent.
+
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{bail, ensure, Result};
+use clap::ValueEnum;
+use risc0_zkvm::{BonsaiProver, ExternalProver, LocalProver, Prover};
+
+/// Prover backend of a stage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ProverBackend {
+    /// Prove on this machine, in this process.
+    Local,
+    /// Prove remotely with the Bonsai proving service, configured with the
+    /// `BONSAI_API_KEY` and `BONSAI_API_URL` environment variables.
+    Bonsai,
+    /// Produce fake receipts on this machine, for development only.
+    DevMode,
+    /// Prove on this machine, with an external `r0vm` process found at
+    /// `RISC0_SERVER_PATH`, or on the `PATH`.
+    External,
+}
+
+impl fmt::Display for ProverBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Bonsai => write!(f, "bonsai"),
+            Self::DevMode => write!(f, "dev-mode"),
+            Self::External => write!(f, "external"),
+        }
+    }
+}
+
+impl ProverBackend {
+    /// Returns the prover of this backend, or an error explaining why it is
+    /// unavailable.
+    ///
+    /// The dev-mode prover requires dev mode to be enabled at startup, with
+    /// [init_dev_mode].
+    pub fn prover(self) -> Result<Rc<dyn Prover>> {
+        self.check_env(env::var_os)?;
+
+        Ok(match self {
+            Self::Local => Rc::new(LocalProver::new("local")),
+            Self::Bonsai => Rc::new(BonsaiProver::new("bonsai")),
+            Self::DevMode => Rc::new(LocalProver::new("dev-mode")),
+            Self::External => Rc::new(ExternalProver::new("external", r0vm_path(env::var_os)?)),
+        })
+    }
+
+    /// Checks that the backend is available, without proving anything.
+    pub fn check_available(self) -> Result<()> {
+        self.prover().map(drop)
+    }
+
+    /// Returns whether the backend produces receipts that are not real proofs.
+    pub fn is_dev_mode(self) -> bool {
+        self == Self::DevMode
+    }
+
+    /// Checks the environment the backend depends on, read through `var`.
+    fn check_env(self, var: impl Fn(&str) -> Option<OsString>) -> Result<()> {
+        // `RISC0_DEV_MODE` silently turns every zkVM prover into a fake one.
+        let dev_mode = dev_mode_enabled(&var);
+        ensure!(
+            self == Self::DevMode || !dev_mode,
+            "RISC0_DEV_MODE is set, which would fake the proofs of the {self} prover: \
+             unset it, or select the dev-mode prover"
+        );
+
+        match self {
+            Self::Local => {}
+            Self::Bonsai => {
+                for name in ["BONSAI_API_KEY", "BONSAI_API_URL"] {
+                    ensure!(
+                        var(name).is_some(),
+                        "the bonsai prover requires the {name} environment variable"
+                    );
+                }
+            }
+            Self::DevMode => ensure!(
+                dev_mode,
+                "dev mode is not enabled: call init_dev_mode before starting any thread"
+            ),
+            Self::External => drop(r0vm_path(&var)?),
+        }
+        Ok(())
+    }
+}
+
+/// Enables the dev mode of the zkVM if any of the `backends` is the dev-mode
+/// prover.
+///
+/// This sets `RISC0_DEV_MODE` for the whole process, so it must be called at
+/// the start of `main`, before any other thread is started.
+pub fn init_dev_mode(backends: &[ProverBackend]) {
+    if backends.iter().any(|backend| backend.is_dev_mode()) {
+        env::set_var("RISC0_DEV_MODE", "1");
+    }
+}
+
+/// Checks that the backends of the two stages can be composed: receipts of
+/// the dev-mode prover can only be composed by the dev-mode prover, and the
+/// other way around.
+pub fn check_composable(local: ProverBackend, remote: ProverBackend) -> Result<()> {
+    ensure!(
+        local.is_dev_mode() == remote.is_dev_mode(),
+        "the {local} and {remote} provers cannot be composed: \
+         select the dev-mode prover for both stages, or for neither"
+    );
+    Ok(())
+}
+
+fn dev_mode_enabled(var: impl Fn(&str) -> Option<OsString>) -> bool {
+    var("RISC0_DEV_MODE")
+        .and_then(|value| value.into_string().ok())
+        .map(|value| !value.is_empty() && value != "0" && !value.eq_ignore_ascii_case("false"))
+        .unwrap_or(false)
+}
+
+/// Finds the `r0vm` binary used by the external prover.
+fn r0vm_path(var: impl Fn(&str) -> Option<OsString>) -> Result<PathBuf> {
+    if let Some(path) = var("RISC0_SERVER_PATH") {
+        let path = PathBuf::from(path);
+        ensure!(
+            path.is_file(),
+            "RISC0_SERVER_PATH points to {}, which is not a file",
+            path.display()
+        );
+        return Ok(path);
+    }
+
+    if let Some(paths) = var("PATH") {
+        if let Some(path) = env::split_paths(&paths)
+            .map(|dir| dir.join("r0vm"))
+            .find(|path| Path::is_file(path))
+        {
+            return Ok(path);
+        }
+    }
+    bail!("the external prover requires r0vm: install it, or set RISC0_SERVER_PATH")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Returns an environment lookup over the given variables only.
+    fn vars<const N: usize>(vars: [(&str, &str); N]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.into()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn rejects_exported_dev_mode() {
+        let env = vars([("RISC0_DEV_MODE", "1")]);
+        for backend in [
+            ProverBackend::Local,
+            ProverBackend::Bonsai,
+            ProverBackend::External,
+        ] {
+            let err = backend.check_env(&env).unwrap_err();
+            assert!(
+                err.to_string().starts_with("RISC0_DEV_MODE is set"),
+                "{err}"
+            );
+        }
+        ProverBackend::DevMode.check_env(&env).unwrap();
+
+        let env = vars([("RISC0_DEV_MODE", "false")]);
+        ProverBackend::Local.check_env(&env).unwrap();
+        ProverBackend::DevMode.check_env(&env).unwrap_err();
+    }
+
+    #[test]
+    fn requires_bonsai_credentials() {
+        let err = ProverBackend::Bonsai
+            .check_env(vars([("BONSAI_API_URL", "https://api.bonsai.xyz")]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the bonsai prover requires the BONSAI_API_KEY environment variable"
+        );
+
+        let err = ProverBackend::Bonsai
+            .check_env(vars([("BONSAI_API_KEY", "key")]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the bonsai prover requires the BONSAI_API_URL environment variable"
+        );
+
+        ProverBackend::Bonsai
+            .check_env(vars([
+                ("BONSAI_API_KEY", "key"),
+                ("BONSAI_API_URL", "https://api.bonsai.xyz"),
+            ]))
+            .unwrap();
+    }
+
+    #[test]
+    fn requires_r0vm() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+
+        let err = ProverBackend::External
+            .check_env(vars([("PATH", path)]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the external prover requires r0vm: install it, or set RISC0_SERVER_PATH"
+        );
+
+        let err = ProverBackend::External
+            .check_env(vars([("RISC0_SERVER_PATH", path)]))
+            .unwrap_err();
+        assert!(err.to_string().ends_with("which is not a file"), "{err}");
+
+        let r0vm = dir.path().join("r0vm");
+        std::fs::write(&r0vm, "").unwrap();
+        assert_eq!(r0vm_path(vars([("PATH", path)])).unwrap(), r0vm);
+    }
+
+    #[test]
+    fn composes_dev_mode_only_with_dev_mode() {
+        use ProverBackend::*;
+
+        check_composable(DevMode, DevMode).unwrap();
+        check_composable(Local, Bonsai).unwrap();
+        check_composable(External, Local).unwrap();
+        check_composable(DevMode, Bonsai).unwrap_err();
+        check_composable(Local, DevMode).unwrap_err();
+    }
+}
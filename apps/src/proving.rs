@@ -0,0 +1,144 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, io::Write, path::Path};
+
+use anyhow::{Context, Result};
+use composition_core::{IsEvenInput, PowerModulusInput, PowerModulusJournal};
+use methods::{IS_EVEN_ELF, POWER_MODULUS_ELF, POWER_MODULUS_ID};
+use risc0_zkvm::{ExecutorEnv, ProverOpts, Receipt};
+
+use crate::{privacy::StageEnv, prover::ProverBackend};
+
+/// Proves the power_modulus guest over the given private input.
+///
+/// The input is validated before proving. The returned receipt is succinct, so
+/// that it is cheap to hand over to the server for composition.
+pub fn prove_power_modulus(input: &PowerModulusInput, backend: ProverBackend) -> Result<Receipt> {
+    // Validate the input on the host, before spending time proving.
+    input.validate().context("invalid power_modulus input")?;
+
+    let env = ExecutorEnv::builder().write(input)?.build()?;
+
+    //  Explicitly prove using private inputs, which never leave this machine.
+    let receipt = StageEnv::private("power_modulus", env).prove(
+        backend,
+        POWER_MODULUS_ELF,
+        &ProverOpts::succinct(),
+    )?;
+
+    if let Some(commitment) = input.commitment() {
+        log::info!("Committed to x with sha256(x || salt): {commitment}");
+    }
+
+    Ok(receipt)
+}
+
+/// Builds the `ExecutorEnv` of the is_even guest, composing the given
+/// power_modulus receipt.
+///
+/// The receipt's journal is written as part of the `IsEvenInput`, which is
+/// read back by the guest to verify the assumption.
+pub fn is_even_env<'a>(assumption: Receipt) -> Result<ExecutorEnv<'a>> {
+    let journal = PowerModulusJournal::decode(&assumption.journal.bytes)
+        .context("decoding power_modulus journal")?;
+
+    ExecutorEnv::builder()
+        .add_assumption(assumption)
+        .write(&IsEvenInput::new(journal))?
+        .build()
+}
+
+/// Composes the given power_modulus receipt into an is_even Groth16 receipt.
+///
+/// The receipt is verified first, so that a bad receipt from a client is
+/// rejected before spending time proving.
+pub fn compose(assumption: Receipt, backend: ProverBackend) -> Result<Receipt> {
+    assumption
+        .verify(POWER_MODULUS_ID)
+        .context("verifying power_modulus receipt")?;
+    compose_verified(assumption, backend)
+}
+
+/// Composes the given power_modulus receipt, which the caller already
+/// verified, into an is_even Groth16 receipt.
+pub fn compose_verified(assumption: Receipt, backend: ProverBackend) -> Result<Receipt> {
+    // The power_modulus receipt only reveals public values, so this stage may
+    // be proven remotely.
+    StageEnv::public("is_even", is_even_env(assumption)?).prove(
+        backend,
+        IS_EVEN_ELF,
+        &ProverOpts::groth16(),
+    )
+}
+
+/// Writes the receipt to the given file, using bincode.
+pub fn write_receipt(path: &Path, receipt: &Receipt) -> Result<()> {
+    let bytes = bincode::serialize(receipt).context("serializing receipt")?;
+    fs::write(path, bytes).with_context(|| format!("writing receipt {}", path.display()))
+}
+
+/// Reads a receipt written by [write_receipt].
+pub fn read_receipt(path: &Path) -> Result<Receipt> {
+    let bytes = fs::read(path).with_context(|| format!("reading receipt {}", path.display()))?;
+    bincode::deserialize(&bytes)
+        .with_context(|| format!("deserializing receipt {}", path.display()))
+}
+
+/// Reads the salt stored in the given file, or generates a random salt and
+/// stores it there if the file does not exist yet. On unix, the new file is
+/// only readable by its owner.
+pub fn load_or_create_salt(path: &Path) -> Result<[u8; 32]> {
+    if path.exists() {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("reading salt file {}", path.display()))?;
+        let mut salt = [0u8; 32];
+        hex::decode_to_slice(contents.trim(), &mut salt)
+            .with_context(|| format!("decoding salt file {}", path.display()))?;
+        return Ok(salt);
+    }
+
+    let salt: [u8; 32] = rand::random();
+    // The salt hides x, so keep it readable by its owner only.
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(hex::encode(salt).as_bytes()))
+        .with_context(|| format!("writing salt file {}", path.display()))?;
+    log::info!("Generated new salt in {}", path.display());
+    Ok(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load_or_create_salt;
+
+    #[test]
+    fn creates_private_salt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("salt");
+        let salt = load_or_create_salt(&path).unwrap();
+        assert_eq!(load_or_create_salt(&path).unwrap(), salt);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of the revert data of failed `IEvenNumber.set` calls and
+//! transactions into readable errors.
+//!
+//! `EvenNumber` declares no errors of its own: its `set` function reverts with
+//! the errors of the verifier, or with a `require` message or a panic.
+
+use std::fmt;
+
+use alloy_sol_types::{sol, Panic, Revert, SolError, SolInterface};
+
+sol! {
+    /// Errors raised by the RISC Zero verifier contracts.
+    interface IRiscZeroVerifier {
+        /// The cryptographic verification of the seal failed.
+        error VerificationFailed();
+        /// The seal was produced for another Groth16 verifier version.
+        error SelectorMismatch(bytes4 received, bytes4 expected);
+        /// The verifier router has no verifier for the seal's selector.
+        error SelectorUnknown(bytes4 selector);
+        /// The verifier router removed the verifier for the seal's selector.
+        error SelectorRemoved(bytes4 selector);
+    }
+}
+
+/// Readable reason of a reverted call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RevertReason(String);
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RevertReason {}
+
+impl RevertReason {
+    /// Decodes the revert data of a call against the known contract errors.
+    pub fn decode(data: &[u8]) -> Self {
+        use IRiscZeroVerifier::IRiscZeroVerifierErrors as VerifierError;
+
+        let reason = if data.is_empty() {
+            "reverted without data".to_string()
+        } else if let Ok(err) = VerifierError::abi_decode(data, true) {
+            match err {
+                VerifierError::VerificationFailed(_) => {
+                    "seal rejected: verification failed".to_string()
+                }
+                VerifierError::SelectorMismatch(err) => format!(
+                    "seal rejected: selector mismatch (received {}, expected {})",
+                    err.received, err.expected
+                ),
+                VerifierError::SelectorUnknown(err) => {
+                    format!("seal rejected: unknown selector {}", err.selector)
+                }
+                VerifierError::SelectorRemoved(err) => {
+                    format!("seal rejected: selector {} was removed", err.selector)
+                }
+            }
+        } else if let Ok(revert) = Revert::abi_decode(data, true) {
+            format!("reverted: {}", revert.reason)
+        } else if let Ok(panic) = Panic::abi_decode(data, true) {
+            format!("panicked with code {}", panic.code)
+        } else {
+            format!("reverted with unknown data 0x{}", hex::encode(data))
+        };
+        Self(reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::FixedBytes;
+    use alloy_sol_types::{Revert, SolError};
+
+    use super::{IRiscZeroVerifier, RevertReason};
+
+    #[test]
+    fn decodes_verifier_errors() {
+        let data = IRiscZeroVerifier::VerificationFailed {}.abi_encode();
+        assert_eq!(
+            RevertReason::decode(&data).to_string(),
+            "seal rejected: verification failed"
+        );
+
+        let data = IRiscZeroVerifier::SelectorMismatch {
+            received: FixedBytes([1, 2, 3, 4]),
+            expected: FixedBytes([5, 6, 7, 8]),
+        }
+        .abi_encode();
+        assert_eq!(
+            RevertReason::decode(&data).to_string(),
+            "seal rejected: selector mismatch (received 0x01020304, expected 0x05060708)"
+        );
+    }
+
+    #[test]
+    fn decodes_revert_string() {
+        let data = Revert::from("not allowed").abi_encode();
+        assert_eq!(
+            RevertReason::decode(&data).to_string(),
+            "reverted: not allowed"
+        );
+    }
+
+    #[test]
+    fn decodes_unknown_data() {
+        assert_eq!(
+            RevertReason::decode(&[0xde, 0xad]).to_string(),
+            "reverted with unknown data 0xdead"
+        );
+        assert_eq!(
+            RevertReason::decode(&[]).to_string(),
+            "reverted without data"
+        );
+    }
+}
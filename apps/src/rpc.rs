@@ -0,0 +1,496 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON-RPC client failing over between several Ethereum node endpoints, so
+//! that a flaky node does not lose a proof that was already paid for.
+//!
+//! The transport of each endpoint is selected from the scheme of its URL:
+//! `http://` and `https://` for HTTP, `ws://` and `wss://` for WebSocket, and
+//! `ipc://` or a plain file path for an IPC socket. WebSocket and IPC
+//! endpoints also serve new block subscriptions, to wait for receipts without
+//! polling. Endpoints that cannot be connected to are kept, and connected to
+//! again when next tried, so that a node restarting does not lose them.
+
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
+use ethers::{prelude::*, providers::StreamExt};
+use futures::future::join_all;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::watch;
+use url::Url;
+
+/// Node endpoint of an RPC URL, with its transport selected from the scheme.
+#[derive(Clone, Debug)]
+enum Target {
+    Http(Http),
+    Ws(String),
+    Ipc(PathBuf),
+}
+
+impl Target {
+    /// Parses the given RPC URL, without connecting to it.
+    fn parse(url: &str) -> Result<Self> {
+        let target = match url.split_once("://") {
+            Some(("http" | "https", _)) => Self::Http(Http::new(Url::parse(url)?)),
+            Some(("ws" | "wss", _)) => {
+                Url::parse(url)?;
+                Self::Ws(url.to_string())
+            }
+            Some(("ipc", path)) => Self::Ipc(path.into()),
+            Some((scheme, _)) => bail!("unsupported RPC URL scheme {scheme}://"),
+            None => {
+                // `localhost:8545` is more likely a URL missing its scheme than a socket path.
+                let has_port = url
+                    .rsplit_once(':')
+                    .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+                ensure!(
+                    !has_port,
+                    "RPC URL has no scheme: use http://{url} or ws://{url}"
+                );
+                Self::Ipc(url.into())
+            }
+        };
+        Ok(target)
+    }
+
+    /// Connects to the endpoint.
+    async fn connect(&self) -> Result<Transport, ProviderError> {
+        let transport = match self {
+            Self::Http(client) => Transport::Http(client.clone()),
+            Self::Ws(url) => Transport::Ws(Ws::connect(url).await?),
+            Self::Ipc(path) => Transport::Ipc(Ipc::connect(path).await?),
+        };
+        Ok(transport)
+    }
+}
+
+/// Connected transport of a node endpoint.
+#[derive(Clone, Debug)]
+enum Transport {
+    Http(Http),
+    Ws(Ws),
+    Ipc(Ipc),
+}
+
+impl Transport {
+    async fn request<R>(&self, method: &str, params: serde_json::Value) -> Result<R, ProviderError>
+    where
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Self::Http(client) => Ok(client.request(method, params).await?),
+            Self::Ws(client) => Ok(client.request(method, params).await?),
+            Self::Ipc(client) => Ok(client.request(method, params).await?),
+        }
+    }
+}
+
+/// Node endpoint, marked unhealthy when it fails to answer a request, and
+/// healthy again once it answers.
+///
+/// WebSocket and IPC endpoints are connected lazily, and connected again
+/// after a failure.
+struct Endpoint {
+    url: String,
+    target: Target,
+    transport: Mutex<Option<Transport>>,
+    healthy: AtomicBool,
+}
+
+impl Endpoint {
+    /// Returns the transport of the endpoint, connecting to it if needed.
+    async fn transport(&self) -> Result<Transport, ProviderError> {
+        if let Some(transport) = self.transport.lock().unwrap().clone() {
+            return Ok(transport);
+        }
+        let transport = self.target.connect().await?;
+        *self.transport.lock().unwrap() = Some(transport.clone());
+        Ok(transport)
+    }
+
+    async fn request<R>(&self, method: &str, params: serde_json::Value) -> Result<R, ProviderError>
+    where
+        R: DeserializeOwned + Send,
+    {
+        let answer = match self.transport().await {
+            Ok(transport) => transport.request(method, params).await,
+            Err(err) => Err(err),
+        };
+        // Error responses, such as reverts, are answers.
+        let answered = match &answer {
+            Ok(_) => true,
+            Err(err) => err.is_error_response(),
+        };
+        self.healthy.store(answered, Ordering::Relaxed);
+        if !answered {
+            // Drop the connection, to connect again on the next request.
+            self.transport.lock().unwrap().take();
+        }
+        answer
+    }
+}
+
+/// JSON-RPC client sending each request to the first healthy endpoint that
+/// answers it, in order.
+///
+/// An endpoint that fails to answer, e.g. because it is down or times out, is
+/// marked unhealthy, and the request is sent to the next one. Unhealthy
+/// endpoints are tried last, and marked healthy again once they answer. Error
+/// responses, such as reverts, are answers, and are returned as is.
+#[derive(Clone)]
+pub struct FailoverClient {
+    endpoints: Arc<Vec<Endpoint>>,
+}
+
+impl fmt::Debug for FailoverClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.endpoints.iter().map(|endpoint| &endpoint.url))
+            .finish()
+    }
+}
+
+impl FailoverClient {
+    /// Connects to the given endpoint URLs, in order of preference. Fails on
+    /// invalid URLs, while endpoints that cannot be connected to are kept,
+    /// marked unhealthy, and connected to again when they are next tried.
+    ///
+    /// WebSocket and IPC connections are served by the current tokio runtime,
+    /// and must not outlive it.
+    pub async fn connect(urls: &[String]) -> Result<Self> {
+        ensure!(!urls.is_empty(), "no RPC URL");
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                Ok(Endpoint {
+                    url: url.clone(),
+                    target: Target::parse(url).with_context(|| format!("invalid RPC URL {url}"))?,
+                    transport: Mutex::new(None),
+                    healthy: AtomicBool::new(true),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for endpoint in &endpoints {
+            if let Err(err) = endpoint.transport().await {
+                log::warn!("Connecting to RPC endpoint {} failed: {err}", endpoint.url);
+                endpoint.healthy.store(false, Ordering::Relaxed);
+            }
+        }
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+        })
+    }
+
+    /// Returns the number of configured endpoints.
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Returns the number of endpoints that answered their last request.
+    pub fn healthy_count(&self) -> usize {
+        self.endpoints
+            .iter()
+            .filter(|endpoint| endpoint.healthy.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Sends the request to every endpoint at once, to compare their answers.
+    pub async fn request_each<T, R>(&self, method: &str, params: T) -> Vec<Result<R, ProviderError>>
+    where
+        T: Serialize,
+        R: DeserializeOwned + Send,
+    {
+        let params = match serde_json::to_value(params) {
+            Ok(params) => params,
+            Err(err) => return vec![Err(err.into())],
+        };
+        join_all(
+            self.endpoints
+                .iter()
+                .map(|endpoint| endpoint.request(method, params.clone())),
+        )
+        .await
+    }
+
+    /// Checks that each endpoint answers, on the given chain, and marks it
+    /// healthy or not. Fails if no endpoint is healthy.
+    pub async fn check_health(&self, chain_id: u64) -> Result<()> {
+        let mut healthy = 0;
+        for endpoint in self.endpoints.iter() {
+            let answer: Result<U256, _> = endpoint
+                .request("eth_chainId", serde_json::Value::Null)
+                .await;
+            let is_healthy = match answer {
+                Ok(id) if id == U256::from(chain_id) => true,
+                Ok(id) => {
+                    log::warn!(
+                        "RPC endpoint {} is on chain {id}, not {chain_id}",
+                        endpoint.url
+                    );
+                    false
+                }
+                Err(err) => {
+                    log::warn!("RPC endpoint {} is unhealthy: {err}", endpoint.url);
+                    false
+                }
+            };
+            endpoint.healthy.store(is_healthy, Ordering::Relaxed);
+            healthy += usize::from(is_healthy);
+        }
+        log::info!(
+            "{healthy}/{} RPC endpoints are healthy",
+            self.endpoints.len()
+        );
+        ensure!(healthy > 0, "no healthy RPC endpoint");
+        Ok(())
+    }
+
+    /// Subscribes to new blocks on the first healthy WebSocket or IPC
+    /// endpoint, if any. The returned receiver is updated with the number of
+    /// each new block, and is closed if the subscription ends.
+    pub async fn new_heads(&self) -> Option<watch::Receiver<U64>> {
+        for endpoint in self.endpoints.iter() {
+            if !endpoint.healthy.load(Ordering::Relaxed)
+                || matches!(endpoint.target, Target::Http(_))
+            {
+                continue;
+            }
+            let (sender, receiver) = watch::channel(U64::zero());
+            let url = endpoint.url.clone();
+            match endpoint.transport().await {
+                Ok(Transport::Ws(client)) => {
+                    tokio::spawn(forward_new_heads(Provider::new(client), sender, url));
+                }
+                Ok(Transport::Ipc(client)) => {
+                    tokio::spawn(forward_new_heads(Provider::new(client), sender, url));
+                }
+                Ok(Transport::Http(_)) => continue,
+                Err(err) => {
+                    log::warn!("Connecting to RPC endpoint {url} failed: {err}");
+                    continue;
+                }
+            }
+            return Some(receiver);
+        }
+        None
+    }
+}
+
+/// Forwards the number of each new block of the provider's subscription,
+/// until the subscription or the receiver is dropped.
+async fn forward_new_heads<P: PubsubClient>(
+    provider: Provider<P>,
+    sender: watch::Sender<U64>,
+    url: String,
+) {
+    let mut blocks = match provider.subscribe_blocks().await {
+        Ok(blocks) => blocks,
+        Err(err) => {
+            log::warn!("Subscribing to new blocks on {url} failed: {err}");
+            return;
+        }
+    };
+    log::debug!("Subscribed to new blocks on {url}");
+    while let Some(block) = blocks.next().await {
+        if sender.send(block.number.unwrap_or_default()).is_err() {
+            return;
+        }
+    }
+    log::warn!("Subscription to new blocks on {url} ended");
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverClient {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = self
+            .endpoints
+            .iter()
+            .partition(|endpoint| endpoint.healthy.load(Ordering::Relaxed));
+
+        let mut last_err = None;
+        for endpoint in healthy.into_iter().chain(unhealthy) {
+            match endpoint.request(method, params.clone()).await {
+                Ok(answer) => return Ok(answer),
+                Err(err) if err.is_error_response() => return Err(err),
+                Err(err) => {
+                    log::warn!("RPC endpoint {} failed on {method}: {err}", endpoint.url);
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.expect("there is at least one endpoint"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use ethers::prelude::*;
+    use serde_json::json;
+    use tokio::sync::broadcast;
+
+    use super::{FailoverClient, Target};
+    use crate::mock_node::{method_not_found, serve_http, serve_ipc, Handler};
+
+    /// Answers as a node on chain 31337, at block 42.
+    fn node() -> Handler {
+        Arc::new(|method, _| match method {
+            "eth_chainId" => Ok(json!("0x7a69")),
+            "eth_blockNumber" => Ok(json!("0x2a")),
+            _ => Err(method_not_found()),
+        })
+    }
+
+    /// Returns the URL of a closed port.
+    async fn dead_node() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[test]
+    fn selects_transport_from_scheme() {
+        let parse = |url: &str| Target::parse(url).map_err(|err| err.to_string());
+
+        assert!(matches!(
+            parse("http://localhost:8545"),
+            Ok(Target::Http(_))
+        ));
+        assert!(matches!(
+            parse("https://rpc.example.com/key"),
+            Ok(Target::Http(_))
+        ));
+        assert!(
+            matches!(parse("ws://localhost:8546"), Ok(Target::Ws(url)) if url == "ws://localhost:8546")
+        );
+        assert!(
+            matches!(parse("ipc:///tmp/geth.ipc"), Ok(Target::Ipc(path)) if path == PathBuf::from("/tmp/geth.ipc"))
+        );
+        assert!(
+            matches!(parse("/tmp/geth.ipc"), Ok(Target::Ipc(path)) if path == PathBuf::from("/tmp/geth.ipc"))
+        );
+        assert!(
+            matches!(parse("geth.ipc"), Ok(Target::Ipc(path)) if path == PathBuf::from("geth.ipc"))
+        );
+
+        assert_eq!(
+            parse("htp://localhost:8545").unwrap_err(),
+            "unsupported RPC URL scheme htp://"
+        );
+        assert_eq!(
+            parse("localhost:8545").unwrap_err(),
+            "RPC URL has no scheme: use http://localhost:8545 or ws://localhost:8545"
+        );
+        assert!(parse("http://").is_err());
+        assert!(parse("ws://local host").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_urls() {
+        let urls = [
+            "http://localhost:8545".to_string(),
+            "htp://localhost:8545".to_string(),
+        ];
+        let err = FailoverClient::connect(&urls).await.unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "invalid RPC URL htp://localhost:8545: unsupported RPC URL scheme htp://"
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_endpoint() {
+        let urls = [
+            dead_node().await,
+            format!("http://{}", serve_http(node()).await),
+        ];
+        let client = FailoverClient::connect(&urls).await.unwrap();
+        client.check_health(31337).await.unwrap();
+
+        let provider = Provider::new(client);
+        assert_eq!(provider.get_block_number().await.unwrap(), U64::from(42));
+    }
+
+    #[tokio::test]
+    async fn connects_to_endpoints_lazily() {
+        // The IPC node is not started yet, but its endpoint is kept.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.ipc");
+        let urls = [path.to_str().unwrap().to_string()];
+        let client = FailoverClient::connect(&urls).await.unwrap();
+        assert_eq!(client.endpoint_count(), 1);
+        client.check_health(31337).await.unwrap_err();
+
+        serve_ipc(&path, node(), broadcast::channel(1).0);
+        client.check_health(31337).await.unwrap();
+        let provider = Provider::new(client);
+        assert_eq!(provider.get_block_number().await.unwrap(), U64::from(42));
+    }
+
+    #[tokio::test]
+    async fn subscribes_to_new_heads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.ipc");
+        let (heads, _) = broadcast::channel(16);
+        serve_ipc(&path, node(), heads.clone());
+
+        let urls = [dead_node().await, format!("ipc://{}", path.display())];
+        let client = FailoverClient::connect(&urls).await.unwrap();
+        let mut new_heads = client.new_heads().await.unwrap();
+
+        // Blocks sent before the subscription is set up are missed, so keep sending.
+        let block = Block::<TxHash> {
+            number: Some(43.into()),
+            hash: Some(H256::repeat_byte(43)),
+            ..Default::default()
+        };
+        let sender = tokio::spawn(async move {
+            loop {
+                heads.send(serde_json::to_value(&block).unwrap()).ok();
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        });
+        new_heads.changed().await.unwrap();
+        assert_eq!(*new_heads.borrow(), U64::from(43));
+        sender.abort();
+    }
+
+    #[tokio::test]
+    async fn rejects_endpoints_on_another_chain() {
+        let urls = [format!("http://{}", serve_http(node()).await)];
+        let client = FailoverClient::connect(&urls).await.unwrap();
+        assert_eq!(
+            client.check_health(1).await.unwrap_err().to_string(),
+            "no healthy RPC endpoint"
+        );
+    }
+}
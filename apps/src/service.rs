@@ -0,0 +1,384 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP service composing client power_modulus receipts into publishable
+//! is_even Groth16 proofs.
+//!
+//! * `POST /jobs` takes a bincode-serialized power_modulus receipt, as written
+//!   by the `client`, and returns the status of the new job.
+//! * `GET /jobs/{id}` returns the status of the job.
+//! * `GET /jobs/{id}/result` returns the encoded seal and the journal of a
+//!   succeeded job, as passed to `IEvenNumber.set`.
+//!
+//! Finished jobs are forgotten after [JOB_TTL], and new jobs are refused while
+//! [MAX_JOBS] jobs are kept.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use methods::POWER_MODULUS_ID;
+use risc0_zkvm::Receipt;
+use serde::Serialize;
+use tokio::sync::{Mutex, Semaphore};
+use uuid::Uuid;
+
+use crate::{encode_seal, prover::ProverBackend, proving::compose_verified};
+
+/// Time for which the status and result of a finished job are kept.
+pub const JOB_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum number of jobs kept, finished or not.
+pub const MAX_JOBS: usize = 1024;
+
+/// Status of a composition job.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free proving slot.
+    Pending,
+    /// Being proven.
+    Running,
+    /// Proven; the result is available.
+    Succeeded,
+    /// Proving failed.
+    Failed { error: String },
+}
+
+/// Publishable proof of a succeeded job.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobResult {
+    /// Hex-encoded seal, prefixed with the verifier selector.
+    pub seal: String,
+    /// Hex-encoded ABI journal of the is_even receipt.
+    pub journal: String,
+}
+
+#[derive(Serialize)]
+struct JobResponse {
+    id: Uuid,
+    #[serde(flatten)]
+    status: JobStatus,
+}
+
+struct Job {
+    status: JobStatus,
+    result: Option<JobResult>,
+    finished_at: Option<Instant>,
+}
+
+/// Shared state of the service.
+#[derive(Clone)]
+pub struct AppState {
+    prover: ProverBackend,
+    jobs: Arc<Mutex<HashMap<Uuid, Job>>>,
+    slots: Arc<Semaphore>,
+    job_ttl: Duration,
+}
+
+impl AppState {
+    /// Creates the state of a service composing with the given prover, at most
+    /// `max_concurrent_jobs` jobs at a time.
+    ///
+    /// Panics if `max_concurrent_jobs` is 0, as no job would ever run.
+    pub fn new(prover: ProverBackend, max_concurrent_jobs: usize) -> Self {
+        assert!(
+            max_concurrent_jobs > 0,
+            "max_concurrent_jobs must be at least 1"
+        );
+        Self {
+            prover,
+            jobs: Default::default(),
+            slots: Arc::new(Semaphore::new(max_concurrent_jobs)),
+            job_ttl: JOB_TTL,
+        }
+    }
+
+    async fn set_status(&self, id: Uuid, status: JobStatus, result: Option<JobResult>) {
+        if let Some(job) = self.jobs.lock().await.get_mut(&id) {
+            if matches!(status, JobStatus::Succeeded | JobStatus::Failed { .. }) {
+                job.finished_at = Some(Instant::now());
+            }
+            job.status = status;
+            job.result = result;
+        }
+    }
+
+    /// Adds a new pending job, after forgetting the jobs finished for longer
+    /// than the TTL. Fails if too many jobs are kept.
+    async fn add_job(&self) -> Result<Uuid, ApiError> {
+        let mut jobs = self.jobs.lock().await;
+        jobs.retain(|_, job| {
+            job.finished_at
+                .map_or(true, |finished_at| finished_at.elapsed() < self.job_ttl)
+        });
+        if jobs.len() >= MAX_JOBS {
+            return Err(ApiError(
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("{} jobs are queued or kept, retry later", jobs.len()),
+            ));
+        }
+
+        let id = Uuid::new_v4();
+        jobs.insert(
+            id,
+            Job {
+                status: JobStatus::Pending,
+                result: None,
+                finished_at: None,
+            },
+        );
+        Ok(id)
+    }
+}
+
+/// Returns the router of the service.
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/jobs", post(create_job))
+        .route("/jobs/:id", get(job_status))
+        .route("/jobs/:id/result", get(job_result))
+        .with_state(state)
+}
+
+/// Error returned to the client, as a JSON body.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        #[derive(Serialize)]
+        struct Body {
+            error: String,
+        }
+        (self.0, Json(Body { error: self.1 })).into_response()
+    }
+}
+
+async fn create_job(
+    State(state): State<AppState>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<JobResponse>), ApiError> {
+    // Reject bad receipts before queueing any proving work. Verifying is
+    // CPU-bound, so keep it off the async executor.
+    let receipt = tokio::task::spawn_blocking(move || {
+        let receipt: Receipt = bincode::deserialize(&body)
+            .map_err(|err| ApiError(StatusCode::BAD_REQUEST, format!("invalid receipt: {err}")))?;
+        receipt.verify(POWER_MODULUS_ID).map_err(|err| {
+            ApiError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("power_modulus receipt does not verify: {err}"),
+            )
+        })?;
+        Ok(receipt)
+    })
+    .await
+    .map_err(|err| ApiError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))??;
+
+    let id = state.add_job().await?;
+    log::info!("Job {id}: accepted");
+
+    tokio::spawn(run_job(state, id, receipt));
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(JobResponse {
+            id,
+            status: JobStatus::Pending,
+        }),
+    ))
+}
+
+async fn run_job(state: AppState, id: Uuid, receipt: Receipt) {
+    let _slot = state
+        .slots
+        .acquire()
+        .await
+        .expect("semaphore is never closed");
+    state.set_status(id, JobStatus::Running, None).await;
+    log::info!("Job {id}: running");
+
+    let backend = state.prover;
+    let outcome = tokio::task::spawn_blocking(move || {
+        // The receipt was verified when the job was created.
+        let receipt = compose_verified(receipt, backend)?;
+        Ok::<_, anyhow::Error>(JobResult {
+            seal: format!("0x{}", hex::encode(encode_seal(&receipt)?)),
+            journal: format!("0x{}", hex::encode(&receipt.journal.bytes)),
+        })
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|outcome| outcome);
+
+    match outcome {
+        Ok(result) => {
+            log::info!("Job {id}: succeeded");
+            state
+                .set_status(id, JobStatus::Succeeded, Some(result))
+                .await;
+        }
+        Err(err) => {
+            log::error!("Job {id}: failed: {err:#}");
+            let status = JobStatus::Failed {
+                error: format!("{err:#}"),
+            };
+            state.set_status(id, status, None).await;
+        }
+    }
+}
+
+async fn job_status(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobResponse>, ApiError> {
+    let jobs = state.jobs.lock().await;
+    let job = jobs
+        .get(&id)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("unknown job {id}")))?;
+
+    Ok(Json(JobResponse {
+        id,
+        status: job.status.clone(),
+    }))
+}
+
+async fn job_result(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobResult>, ApiError> {
+    let jobs = state.jobs.lock().await;
+    let job = jobs
+        .get(&id)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("unknown job {id}")))?;
+
+    job.result.clone().map(Json).ok_or_else(|| {
+        ApiError(
+            StatusCode::CONFLICT,
+            format!("job {id} has no result, see GET /jobs/{id} for its status"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// Returns the state of a service that never reaches its prover.
+    fn state() -> AppState {
+        AppState::new(ProverBackend::DevMode, 1)
+    }
+
+    async fn send(state: &AppState, request: Request<Body>) -> (StatusCode, Value) {
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_receipt() {
+        let request = Request::post("/jobs")
+            .body(Body::from("not a receipt"))
+            .unwrap();
+        let (status, body) = send(&state(), request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid receipt"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_job() {
+        let state = state();
+        let id = Uuid::new_v4();
+        for uri in [format!("/jobs/{id}"), format!("/jobs/{id}/result")] {
+            let (status, body) = send(&state, get(&uri)).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+            assert_eq!(body["error"], format!("unknown job {id}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn has_no_result_before_completion() {
+        let state = state();
+        let id = state.add_job().await.unwrap();
+
+        let (status, body) = send(&state, get(&format!("/jobs/{id}"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "pending");
+
+        let (status, _) = send(&state, get(&format!("/jobs/{id}/result"))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let result = JobResult {
+            seal: "0x01".to_string(),
+            journal: "0x02".to_string(),
+        };
+        state
+            .set_status(id, JobStatus::Succeeded, Some(result))
+            .await;
+        let (status, body) = send(&state, get(&format!("/jobs/{id}/result"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["seal"], "0x01");
+    }
+
+    #[tokio::test]
+    async fn forgets_finished_jobs() {
+        let mut state = state();
+        state.job_ttl = Duration::ZERO;
+        let finished = state.add_job().await.unwrap();
+        let failed = JobStatus::Failed {
+            error: "failed".to_string(),
+        };
+        state.set_status(finished, failed, None).await;
+        let pending = state.add_job().await.unwrap();
+
+        let (status, _) = send(&state, get(&format!("/jobs/{finished}"))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(&state, get(&format!("/jobs/{pending}"))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn refuses_jobs_above_the_cap() {
+        let state = state();
+        for _ in 0..MAX_JOBS {
+            state.add_job().await.unwrap();
+        }
+        let err = state.add_job().await.err().unwrap();
+        assert_eq!(err.0, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
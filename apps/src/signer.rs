@@ -0,0 +1,223 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signers of the transactions sent by a [TxSender](crate::TxSender).
+//!
+//! Transactions are signed either with a [LocalWallet] held by the process,
+//! or by an external signing service over the `eth_signTransaction` JSON-RPC
+//! method, with a [RemoteSigner], so that the publisher never holds the key.
+//! A [ReadOnlyAccount] only estimates and simulates transactions, for dry runs.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use async_trait::async_trait;
+use ethers::{prelude::*, types::transaction::eip2718::TypedTransaction, utils::rlp::Rlp};
+
+/// Signer of the transactions sent by a [TxSender](crate::TxSender).
+#[async_trait]
+pub trait TxSigner: Send + Sync {
+    /// Returns the address of the signing account.
+    fn address(&self) -> Address;
+
+    /// Signs the given transaction, with its sender set to [TxSigner::address].
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature>;
+}
+
+#[async_trait]
+impl TxSigner for LocalWallet {
+    fn address(&self) -> Address {
+        Signer::address(self)
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        Ok(Signer::sign_transaction(self, tx).await?)
+    }
+}
+
+#[async_trait]
+impl<S: TxSigner + ?Sized> TxSigner for Box<S> {
+    fn address(&self) -> Address {
+        (**self).address()
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        (**self).sign_transaction(tx).await
+    }
+}
+
+/// Account known by its address only, to estimate and simulate transactions
+/// from it without any wallet credentials. It refuses to sign.
+#[derive(Clone, Copy, Debug)]
+pub struct ReadOnlyAccount(pub Address);
+
+#[async_trait]
+impl TxSigner for ReadOnlyAccount {
+    fn address(&self) -> Address {
+        self.0
+    }
+
+    async fn sign_transaction(&self, _tx: &TypedTransaction) -> Result<Signature> {
+        bail!(
+            "cannot sign transactions from the read-only account {:?}: select a wallet",
+            self.0
+        )
+    }
+}
+
+/// Signer calling the `eth_signTransaction` method of an external JSON-RPC
+/// signing service, e.g. Clef, Web3Signer or a node with an unlocked account.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    provider: Provider<Http>,
+    address: Address,
+}
+
+impl RemoteSigner {
+    /// Creates a signer calling the signing service at the given URL, to sign
+    /// with the given account.
+    pub fn new(url: &str, address: Address) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(url)?;
+        Ok(Self { provider, address })
+    }
+}
+
+#[async_trait]
+impl TxSigner for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        ensure!(
+            tx.from() == Some(&self.address),
+            "transaction is not sent from the remote signer account {:?}",
+            self.address
+        );
+
+        // Geth returns the raw transaction along with its decoded fields,
+        // while dedicated signers return the raw transaction alone.
+        let response: serde_json::Value = self
+            .provider
+            .request("eth_signTransaction", [tx])
+            .await
+            .context("calling eth_signTransaction on the remote signer")?;
+        let raw = match response.get("raw").unwrap_or(&response) {
+            serde_json::Value::String(raw) => raw.parse::<Bytes>()?,
+            _ => bail!("unexpected eth_signTransaction response: {response}"),
+        };
+
+        // Check that the signer signed the requested transaction, with the
+        // expected account, before using its signature.
+        let (signed, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw))
+            .map_err(|err| anyhow!("decoding the signed transaction: {err}"))?;
+        ensure!(
+            signed.sighash() == tx.sighash(),
+            "the remote signer signed a different transaction"
+        );
+        ensure!(
+            signature.recover(tx.sighash())? == self.address,
+            "the remote signer signed with another account than {:?}",
+            self.address
+        );
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use ethers::{prelude::*, types::transaction::eip2718::TypedTransaction};
+    use serde_json::json;
+
+    use super::{RemoteSigner, TxSigner};
+    use crate::mock_node::{method_not_found, serve_http};
+
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    /// Serves a mock signing service, signing with the given wallet after
+    /// applying `tamper` to the requested transaction.
+    async fn mock_signer(wallet: LocalWallet, tamper: fn(&mut TypedTransaction)) -> SocketAddr {
+        serve_http(Arc::new(move |method, params| {
+            if method != "eth_signTransaction" {
+                return Err(method_not_found());
+            }
+            let mut tx: TypedTransaction = serde_json::from_value(params[0].clone()).unwrap();
+            tamper(&mut tx);
+            let signature = wallet.sign_transaction_sync(&tx).unwrap();
+            Ok(json!({ "raw": tx.rlp_signed(&signature), "tx": tx }))
+        }))
+        .await
+    }
+
+    fn transaction(from: Address) -> TypedTransaction {
+        Eip1559TransactionRequest::new()
+            .chain_id(31337)
+            .from(from)
+            .to(Address::repeat_byte(1))
+            .data(vec![1, 2, 3])
+            .nonce(7)
+            .gas(100_000)
+            .max_fee_per_gas(2_000_000_000u64)
+            .max_priority_fee_per_gas(1_000_000_000u64)
+            .into()
+    }
+
+    #[tokio::test]
+    async fn signs_with_remote_signer() {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let addr = mock_signer(wallet.clone(), |_| {}).await;
+
+        let signer =
+            RemoteSigner::new(&format!("http://{addr}"), Signer::address(&wallet)).unwrap();
+
+        // The remote signature holds the EIP-1559 y-parity, while the wallet's
+        // holds an EIP-155 v, so compare the signer and the signed transaction.
+        let tx = transaction(Signer::address(&wallet));
+        let signature = signer.sign_transaction(&tx).await.unwrap();
+        assert_eq!(
+            signature.recover(tx.sighash()).unwrap(),
+            Signer::address(&wallet)
+        );
+        let expected = Signer::sign_transaction(&wallet, &tx).await.unwrap();
+        assert_eq!(tx.rlp_signed(&signature), tx.rlp_signed(&expected));
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_method() {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let addr = mock_signer(wallet, |_| {}).await;
+
+        let provider = Provider::<Http>::try_from(format!("http://{addr}")).unwrap();
+        let err = provider.get_accounts().await.unwrap_err();
+        assert!(err.as_error_response().is_some());
+    }
+
+    #[tokio::test]
+    async fn rejects_tampered_transaction() {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let addr = mock_signer(wallet.clone(), |tx| {
+            tx.set_to(Address::repeat_byte(2));
+        })
+        .await;
+
+        let signer =
+            RemoteSigner::new(&format!("http://{addr}"), Signer::address(&wallet)).unwrap();
+        let tx = transaction(Signer::address(&wallet));
+        let err = signer.sign_transaction(&tx).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the remote signer signed a different transaction"
+        );
+    }
+}
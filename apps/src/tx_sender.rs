@@ -0,0 +1,1175 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use ethers::{prelude::*, types::transaction::eip2718::TypedTransaction, utils::format_ether};
+
+use crate::{
+    nonce::{NonceStore, SentTransaction},
+    revert::RevertReason,
+    rpc::FailoverClient,
+    signer::TxSigner,
+};
+
+/// Gas of a plain transfer, used to cancel a pending transaction.
+const CANCEL_GAS: u64 = 21_000;
+
+/// Minimum fee bump, in percent, for nodes to accept a replacement transaction.
+const MIN_FEE_BUMP_PERCENT: u64 = 10;
+
+/// Gas and fee settings of the EIP-1559 transactions sent by a [TxSender].
+///
+/// Settings left unset are estimated from the node.
+#[derive(Clone, Debug, Default)]
+pub struct GasOptions {
+    /// Maximum fee per gas, in wei.
+    pub max_fee_per_gas: Option<U256>,
+    /// Maximum priority fee per gas, in wei.
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Gas limit of the transaction.
+    pub gas_limit: Option<U256>,
+    /// Percentage added to the estimated gas to get the gas limit.
+    pub gas_margin_percent: u64,
+    /// Maximum cost of the transaction, `gas_limit * max_fee_per_gas`, in wei.
+    pub max_cost: Option<U256>,
+    /// Time after which a transaction that is not included is replaced with
+    /// higher fees. Transactions are never replaced if not set.
+    pub replace_after: Option<Duration>,
+    /// Percentage by which the fees of a replacement transaction are bumped.
+    /// Raised to the 10% minimum accepted by nodes.
+    pub fee_bump_percent: u64,
+}
+
+/// Signed transaction, kept to broadcast it again.
+struct SignedTransaction {
+    hash: TxHash,
+    raw: Bytes,
+}
+
+/// Wrapper of a `Provider` client to send transactions to the given
+/// contract's `Address`, signed by a [TxSigner].
+pub struct TxSender<S = LocalWallet> {
+    chain_id: u64,
+    client: Provider<FailoverClient>,
+    signer: S,
+    contract: Address,
+    gas: GasOptions,
+    confirmations: u64,
+    confirmation_timeout: Option<Duration>,
+    quorum: Option<usize>,
+    nonces: Option<NonceStore>,
+}
+
+impl<S: TxSigner> TxSender<S> {
+    /// Creates a new `TxSender`, signing transactions with the given signer.
+    ///
+    /// Requests fail over between the given RPC URLs, in order, and the
+    /// confirmation of a transaction requires a majority of the healthy ones
+    /// to agree, unless a quorum is set.
+    /// WebSocket and IPC connections are served by the current tokio runtime,
+    /// so the `TxSender` must not outlive it.
+    pub async fn new(
+        chain_id: u64,
+        rpc_urls: &[String],
+        signer: S,
+        contract: &str,
+    ) -> Result<Self> {
+        let client = Provider::new(FailoverClient::connect(rpc_urls).await?);
+        let contract = contract.parse::<Address>()?;
+
+        Ok(TxSender {
+            chain_id,
+            client,
+            signer,
+            contract,
+            gas: GasOptions::default(),
+            confirmations: 1,
+            confirmation_timeout: None,
+            quorum: None,
+            nonces: None,
+        })
+    }
+
+    /// Sets the number of RPC endpoints that must agree on the block of a
+    /// transaction to confirm it, even if some of them are unhealthy.
+    pub fn with_quorum(mut self, quorum: usize) -> Result<Self> {
+        let endpoints = self.client.as_ref().endpoint_count();
+        ensure!(
+            (1..=endpoints).contains(&quorum),
+            "quorum must be between 1 and the {endpoints} RPC URLs"
+        );
+        self.quorum = Some(quorum);
+        Ok(self)
+    }
+
+    /// Checks the health of the RPC endpoints, failing if none is healthy.
+    pub async fn check_health(&self) -> Result<()> {
+        self.client.as_ref().check_health(self.chain_id).await
+    }
+
+    /// Tracks the transactions sent in the given nonce store.
+    pub fn with_nonce_store(mut self, nonces: NonceStore) -> Self {
+        self.nonces = Some(nonces);
+        self
+    }
+
+    /// Sets the number of confirmations to wait for, including the block that
+    /// includes the transaction.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    /// Sets the time to wait for a transaction to be confirmed, after which
+    /// sending it fails. Transactions are waited for forever if not set.
+    pub fn with_confirmation_timeout(mut self, timeout: Duration) -> Self {
+        self.confirmation_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the gas and fee settings of the transactions sent.
+    pub fn with_gas_options(mut self, gas: GasOptions) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    /// Returns the address of the contract transactions are sent to.
+    pub fn contract(&self) -> Address {
+        self.contract
+    }
+
+    /// Estimates the gas used by a transaction with the given calldata, without
+    /// sending it.
+    pub async fn estimate_gas(&self, calldata: Vec<u8>) -> Result<U256> {
+        let tx: TypedTransaction = self.tx_request(calldata).into();
+        self.client
+            .estimate_gas(&tx, None)
+            .await
+            .map_err(revert_error)
+    }
+
+    /// Simulates a transaction with the given calldata with `eth_call`, failing
+    /// with the decoded revert reason if it would revert.
+    pub async fn simulate(&self, calldata: Vec<u8>) -> Result<()> {
+        let tx: TypedTransaction = self.tx_request(calldata).into();
+        self.client.call(&tx, None).await.map_err(revert_error)?;
+        Ok(())
+    }
+
+    /// Simulates a transaction with the given calldata and sends it if it
+    /// succeeds. With `force`, the transaction is sent even if it would revert.
+    pub async fn simulate_and_send(
+        &self,
+        calldata: Vec<u8>,
+        force: bool,
+    ) -> Result<TransactionReceipt> {
+        if let Err(err) = self.simulate(calldata.clone()).await {
+            if !force {
+                bail!("{err:#}; not sending the transaction, use --force to send it anyway");
+            }
+            log::warn!("{err:#}; sending the transaction anyway");
+        }
+        self.send_with(calldata, force).await
+    }
+
+    /// Sends a transaction with the given calldata, and waits for it to be
+    /// confirmed. Returns the receipt of the confirmed transaction, failing if
+    /// it reverted.
+    pub async fn send(&self, calldata: Vec<u8>) -> Result<TransactionReceipt> {
+        self.send_with(calldata, false).await
+    }
+
+    /// Sends a transaction with the given calldata. With `force`, a transaction
+    /// whose gas estimation reverts is still sent, see [Self::transaction].
+    async fn send_with(&self, calldata: Vec<u8>, force: bool) -> Result<TransactionReceipt> {
+        self.check_health().await?;
+        let tx = self.transaction(calldata, force).await?;
+        let nonce = self.next_nonce().await?;
+
+        log::info!("Transaction request: {:?}", &tx);
+
+        let receipt = self.send_at_nonce(tx.clone().nonce(nonce)).await?;
+
+        log::info!("Transaction receipt: {:?}", &receipt);
+
+        if receipt.status == Some(0.into()) {
+            let hash = receipt.transaction_hash;
+            return Err(match self.revert_reason(&tx, &receipt).await {
+                Some(reason) => reason.context(format!("transaction {hash:?} reverted")),
+                None => anyhow!("transaction {hash:?} reverted, without a known reason"),
+            });
+        }
+
+        Ok(receipt)
+    }
+
+    /// Cancels the pending transaction with the given nonce, or with the
+    /// lowest pending nonce, by replacing it with a zero-value transfer from
+    /// the sender to itself, and waits for the transfer to be confirmed.
+    pub async fn cancel(&self, nonce: Option<U256>) -> Result<TransactionReceipt> {
+        self.check_health().await?;
+        let address = self.signer.address();
+        let nonce = match nonce {
+            Some(nonce) => nonce,
+            None => {
+                let latest = self.transaction_count(BlockNumber::Latest).await?;
+                let pending = self.transaction_count(BlockNumber::Pending).await?;
+                let last = self.last_sent()?.filter(|last| last.nonce >= latest);
+                ensure!(
+                    pending > latest || last.is_some(),
+                    "no pending transaction from {address:?} to cancel"
+                );
+                latest
+            }
+        };
+
+        // Outbid the pending transaction, if it was sent from here.
+        let (mut max_fee_per_gas, mut max_priority_fee_per_gas) = self.fees().await?;
+        if let Some(last) = self.last_sent()?.filter(|last| last.nonce == nonce) {
+            max_fee_per_gas = max_fee_per_gas.max(self.bump_fee(last.max_fee_per_gas));
+            max_priority_fee_per_gas =
+                max_priority_fee_per_gas.max(self.bump_fee(last.max_priority_fee_per_gas));
+        }
+        self.check_cost(CANCEL_GAS.into(), max_fee_per_gas)?;
+
+        let tx = Eip1559TransactionRequest::new()
+            .chain_id(self.chain_id)
+            .from(address)
+            .to(address)
+            .value(0)
+            .gas(CANCEL_GAS)
+            .max_fee_per_gas(max_fee_per_gas)
+            .max_priority_fee_per_gas(max_priority_fee_per_gas)
+            .nonce(nonce);
+
+        log::info!("Cancelling the transaction with nonce {nonce}");
+
+        self.send_at_nonce(tx).await
+    }
+
+    /// Returns the nonce of the next transaction. The nonce of the last
+    /// transaction sent from here is skipped while it is pending, even if the
+    /// node does not count it, e.g. when it sits in another node's mempool.
+    async fn next_nonce(&self) -> Result<U256> {
+        let pending = self.transaction_count(BlockNumber::Pending).await?;
+        let Some(last) = self.last_sent()? else {
+            return Ok(pending);
+        };
+        if last.nonce < pending {
+            return Ok(pending);
+        }
+        if self.client.get_transaction(last.hash).await?.is_some() {
+            log::warn!(
+                "Transaction {:?} with nonce {} is still pending, use the cancel subcommand if it is stuck",
+                last.hash,
+                last.nonce
+            );
+            return Ok(last.nonce + 1);
+        }
+        // The last transaction was dropped, and its nonce can be used again.
+        Ok(pending)
+    }
+
+    /// Sends the given transaction, with its nonce set, and waits for it, or
+    /// one of its replacements, to be included and buried under the configured
+    /// number of confirmations.
+    ///
+    /// While the transaction is not included, it is replaced with higher fees
+    /// after each `replace_after` period. If a reorg drops it once included,
+    /// its last signed version is broadcast again. Fails once the confirmation
+    /// timeout, if any, is reached, whatever prevents the confirmation.
+    async fn send_at_nonce(&self, mut tx: Eip1559TransactionRequest) -> Result<TransactionReceipt> {
+        let interval = self.client.get_interval();
+        let mut new_heads = self.client.as_ref().new_heads().await;
+        let mut sent = vec![self.broadcast(&tx).await?];
+        let mut sent_at = Instant::now();
+        let deadline = self
+            .confirmation_timeout
+            .map(|timeout| (timeout, sent_at + timeout));
+        let mut included: Option<TransactionReceipt> = None;
+        loop {
+            if let Some((timeout, deadline)) = deadline {
+                if Instant::now() >= deadline {
+                    let last = sent.last().expect("at least one transaction was sent");
+                    bail!(
+                        "transaction {:?} was not confirmed within {timeout:?}: check it, or replace it with the cancel subcommand",
+                        last.hash
+                    );
+                }
+            }
+
+            // Wait for the next block when subscribed to new blocks, or poll,
+            // until the deadline.
+            let next_block = async {
+                match &mut new_heads {
+                    Some(heads) => heads.changed().await.is_ok(),
+                    None => {
+                        tokio::time::sleep(interval).await;
+                        true
+                    }
+                }
+            };
+            let subscribed = match deadline {
+                Some((_, deadline)) => {
+                    match tokio::time::timeout_at(deadline.into(), next_block).await {
+                        Ok(subscribed) => subscribed,
+                        Err(_) => continue,
+                    }
+                }
+                None => next_block.await,
+            };
+            if !subscribed {
+                log::warn!("New blocks subscription ended, polling instead");
+                new_heads = None;
+            }
+
+            // The transaction was sent, so failing reads are retried, rather
+            // than failing the whole run.
+            let receipt = match self.find_receipt(&sent).await {
+                Ok(receipt) => receipt,
+                Err(err) => {
+                    log::warn!("Polling the transaction failed, retrying: {err:#}");
+                    continue;
+                }
+            };
+
+            let Some(receipt) = receipt else {
+                let last = sent.last().expect("at least one transaction was sent");
+                if let Some(dropped) = included.take() {
+                    log::warn!(
+                        "Transaction {:?} was dropped from block {:?} by a reorg, broadcasting it again",
+                        dropped.transaction_hash,
+                        dropped.block_hash
+                    );
+                    if let Err(err) = self.client.send_raw_transaction(last.raw.clone()).await {
+                        // The node may already have put the transaction back into its mempool.
+                        log::warn!(
+                            "Broadcasting transaction {:?} again failed: {err}",
+                            last.hash
+                        );
+                    }
+                    sent_at = Instant::now();
+                } else if self
+                    .gas
+                    .replace_after
+                    .is_some_and(|timeout| sent_at.elapsed() >= timeout)
+                {
+                    log::warn!(
+                        "Transaction {:?} is not included after {:?}, replacing it with higher fees",
+                        last.hash,
+                        sent_at.elapsed()
+                    );
+                    match self.replacement(&tx) {
+                        Ok(replacement) => match self.broadcast(&replacement).await {
+                            Ok(signed) => {
+                                tx = replacement;
+                                sent.push(signed);
+                            }
+                            Err(err) => log::warn!("Replacing the transaction failed: {err:#}"),
+                        },
+                        Err(err) => log::warn!("Not replacing the transaction: {err:#}"),
+                    }
+                    sent_at = Instant::now();
+                }
+                continue;
+            };
+
+            let hash = receipt.transaction_hash;
+            let (Some(block_number), Some(block_hash)) = (receipt.block_number, receipt.block_hash)
+            else {
+                continue;
+            };
+            if let Some(previous) = &included {
+                if previous.block_hash != receipt.block_hash {
+                    log::warn!(
+                        "Transaction {hash:?} moved from block {:?} to block {block_hash:?} by a reorg",
+                        previous.block_hash
+                    );
+                }
+            }
+            included = Some(receipt.clone());
+
+            let head = match self.client.get_block_number().await {
+                Ok(head) => head,
+                Err(err) => {
+                    log::warn!("Polling the block number failed, retrying: {err}");
+                    continue;
+                }
+            };
+            let confirmations = (head + 1).saturating_sub(block_number).as_u64();
+            log::debug!(
+                "Transaction {hash:?} has {confirmations}/{} confirmations",
+                self.confirmations
+            );
+            if confirmations < self.confirmations {
+                continue;
+            }
+
+            // Check that the block of the receipt is still canonical, and that
+            // a quorum of the endpoints agrees, before considering the
+            // transaction as confirmed.
+            let canonical = match self
+                .client
+                .get_block(BlockNumber::Number(block_number))
+                .await
+            {
+                Ok(block) => block.and_then(|block| block.hash),
+                Err(err) => {
+                    log::warn!("Polling block {block_number} failed, retrying: {err}");
+                    continue;
+                }
+            };
+            if canonical == Some(block_hash) && self.has_quorum(hash, block_hash).await {
+                log::info!(
+                    "Transaction {hash:?} confirmed in block {block_number} ({block_hash:?}) with {confirmations} confirmations"
+                );
+                return Ok(receipt);
+            }
+        }
+    }
+
+    /// Returns the receipt of any of the transactions sent with the same nonce.
+    async fn find_receipt(&self, sent: &[SignedTransaction]) -> Result<Option<TransactionReceipt>> {
+        for signed in sent {
+            if let Some(receipt) = self.client.get_transaction_receipt(signed.hash).await? {
+                return Ok(Some(receipt));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Checks that at least a quorum of the endpoints report the transaction
+    /// as included in the given block. Without a set quorum, a majority of the
+    /// healthy endpoints is required, so that a backup endpoint being down
+    /// does not block the confirmation.
+    async fn has_quorum(&self, hash: TxHash, block_hash: H256) -> bool {
+        let quorum = self
+            .quorum
+            .unwrap_or_else(|| self.client.as_ref().healthy_count() / 2 + 1);
+        if quorum <= 1 {
+            return true;
+        }
+        let mut agreeing = 0;
+        let receipts = self
+            .client
+            .as_ref()
+            .request_each::<_, Option<TransactionReceipt>>("eth_getTransactionReceipt", [hash])
+            .await;
+        for receipt in receipts {
+            match receipt {
+                Ok(receipt) => {
+                    agreeing += usize::from(
+                        receipt.is_some_and(|receipt| receipt.block_hash == Some(block_hash)),
+                    );
+                }
+                Err(err) => log::debug!("Quorum read failed: {err}"),
+            }
+        }
+        log::debug!(
+            "{agreeing} endpoints report transaction {hash:?} in block {block_hash:?}, {quorum} required"
+        );
+        agreeing >= quorum
+    }
+
+    /// Signs and broadcasts the given transaction, after recording it as the
+    /// last one sent from this account.
+    ///
+    /// An endpoint may accept the transaction but fail to answer, so that the
+    /// next endpoint rejects it as already known, or its nonce as too low. The
+    /// transaction is then considered sent, and its receipt is polled for as
+    /// usual.
+    async fn broadcast(&self, tx: &Eip1559TransactionRequest) -> Result<SignedTransaction> {
+        let mut typed: TypedTransaction = tx.clone().into();
+        self.client.fill_transaction(&mut typed, None).await?;
+        let signature = self.signer.sign_transaction(&typed).await?;
+        let signed = SignedTransaction {
+            hash: typed.hash(&signature),
+            raw: typed.rlp_signed(&signature),
+        };
+
+        if let Some(nonces) = &self.nonces {
+            let sent = SentTransaction {
+                nonce: typed.nonce().copied().unwrap_or_default(),
+                hash: signed.hash,
+                max_fee_per_gas: tx.max_fee_per_gas.unwrap_or_default(),
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or_default(),
+            };
+            nonces.record(self.chain_id, self.signer.address(), sent)?;
+        }
+
+        match self.client.send_raw_transaction(signed.raw.clone()).await {
+            Ok(_) => log::info!("Transaction {:?} sent", signed.hash),
+            Err(err) if is_known_transaction(&err) => log::warn!(
+                "Transaction {:?} may already be sent: {err}; waiting for it",
+                signed.hash
+            ),
+            Err(err) => return Err(err.into()),
+        }
+        Ok(signed)
+    }
+
+    /// Returns the given transaction with its fees bumped, to replace it.
+    fn replacement(&self, tx: &Eip1559TransactionRequest) -> Result<Eip1559TransactionRequest> {
+        let max_fee_per_gas = self.bump_fee(tx.max_fee_per_gas.unwrap_or_default());
+        let max_priority_fee_per_gas =
+            self.bump_fee(tx.max_priority_fee_per_gas.unwrap_or_default());
+        self.check_cost(tx.gas.unwrap_or_default(), max_fee_per_gas)?;
+        Ok(tx
+            .clone()
+            .max_fee_per_gas(max_fee_per_gas)
+            .max_priority_fee_per_gas(max_priority_fee_per_gas))
+    }
+
+    /// Bumps the given fee enough for nodes to accept a replacement.
+    fn bump_fee(&self, fee: U256) -> U256 {
+        let percent = self.gas.fee_bump_percent.max(MIN_FEE_BUMP_PERCENT);
+        fee * (100 + percent) / 100 + 1
+    }
+
+    fn last_sent(&self) -> Result<Option<SentTransaction>> {
+        match &self.nonces {
+            Some(nonces) => nonces.last(self.chain_id, self.signer.address()),
+            None => Ok(None),
+        }
+    }
+
+    async fn transaction_count(&self, block: BlockNumber) -> Result<U256> {
+        Ok(self
+            .client
+            .get_transaction_count(self.signer.address(), Some(block.into()))
+            .await?)
+    }
+
+    /// Retrieves the revert reason of a reverted transaction from its call
+    /// trace or, if the node does not support tracing, by replaying it on top
+    /// of the previous block. A replayed reason is labelled as approximate, as
+    /// the transactions before it in its block are not replayed.
+    async fn revert_reason(
+        &self,
+        tx: &Eip1559TransactionRequest,
+        receipt: &TransactionReceipt,
+    ) -> Option<anyhow::Error> {
+        let options = GethDebugTracingOptions {
+            tracer: Some(GethDebugTracerType::BuiltInTracer(
+                GethDebugBuiltInTracerType::CallTracer,
+            )),
+            ..Default::default()
+        };
+        match self
+            .client
+            .debug_trace_transaction(receipt.transaction_hash, options)
+            .await
+        {
+            Ok(GethTrace::Known(GethTraceFrame::CallTracer(frame))) => {
+                return Some(anyhow::Error::new(RevertReason::decode(
+                    frame.output.as_deref().unwrap_or_default(),
+                )));
+            }
+            Ok(trace) => log::debug!("Unexpected transaction trace: {trace:?}"),
+            Err(err) => log::debug!("Tracing the transaction failed: {err}"),
+        }
+
+        let block = receipt.block_number?.checked_sub(1.into())?;
+        let tx: TypedTransaction = tx.clone().into();
+        match self
+            .client
+            .call(&tx, Some(BlockNumber::Number(block).into()))
+            .await
+        {
+            Ok(_) => None,
+            Err(err) => err
+                .as_error_response()
+                .and_then(JsonRpcError::as_revert_data)
+                .map(|data| {
+                    anyhow::Error::new(RevertReason::decode(&data)).context(format!(
+                        "approximate reason, replayed on top of block {block}"
+                    ))
+                }),
+        }
+    }
+
+    /// Builds the EIP-1559 transaction to send, with its fees and gas limit
+    /// either set from the [GasOptions] or estimated, and checks its maximum
+    /// cost against the budget.
+    ///
+    /// The estimation fails if the transaction would revert. With `force`, the
+    /// gas limit then falls back to the latest block gas limit, capped by the
+    /// budget.
+    async fn transaction(
+        &self,
+        calldata: Vec<u8>,
+        force: bool,
+    ) -> Result<Eip1559TransactionRequest> {
+        let tx = self.tx_request(calldata);
+        let (max_fee_per_gas, max_priority_fee_per_gas) = self.fees().await?;
+
+        let gas_limit = match self.gas.gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => match self.client.estimate_gas(&tx.clone().into(), None).await {
+                Ok(estimate) => estimate * (100 + self.gas.gas_margin_percent) / 100,
+                Err(err) if force => {
+                    log::warn!(
+                        "{:#}; using the block gas limit",
+                        revert_error(err).context("estimating gas")
+                    );
+                    self.fallback_gas_limit(max_fee_per_gas).await?
+                }
+                Err(err) => {
+                    return Err(revert_error(err)
+                        .context("estimating gas, set a gas limit to skip the estimation"))
+                }
+            },
+        };
+
+        self.check_cost(gas_limit, max_fee_per_gas)?;
+        log::info!(
+            "Gas limit: {gas_limit}, max fee per gas: {max_fee_per_gas} wei, max priority fee per gas: {max_priority_fee_per_gas} wei, max cost: {} ETH",
+            format_ether(gas_limit * max_fee_per_gas)
+        );
+
+        Ok(tx
+            .gas(gas_limit)
+            .max_fee_per_gas(max_fee_per_gas)
+            .max_priority_fee_per_gas(max_priority_fee_per_gas))
+    }
+
+    /// Returns the max fee and max priority fee per gas, either set from the
+    /// [GasOptions] or estimated.
+    async fn fees(&self) -> Result<(U256, U256)> {
+        let (max_fee_per_gas, max_priority_fee_per_gas) =
+            match (self.gas.max_fee_per_gas, self.gas.max_priority_fee_per_gas) {
+                (Some(max_fee), Some(priority_fee)) => (max_fee, priority_fee),
+                (max_fee, priority_fee) => {
+                    let (estimated_max_fee, estimated_priority_fee) = self
+                        .client
+                        .estimate_eip1559_fees(None)
+                        .await
+                        .context("estimating fees")?;
+                    (
+                        max_fee.unwrap_or(estimated_max_fee),
+                        priority_fee.unwrap_or(estimated_priority_fee),
+                    )
+                }
+            };
+        ensure!(
+            max_priority_fee_per_gas <= max_fee_per_gas,
+            "max priority fee of {max_priority_fee_per_gas} wei is above the max fee per gas of {max_fee_per_gas} wei"
+        );
+        Ok((max_fee_per_gas, max_priority_fee_per_gas))
+    }
+
+    /// Checks the maximum cost of a transaction against the budget.
+    /// Returns the gas limit of the latest block, capped so that the
+    /// transaction costs at most the budget.
+    async fn fallback_gas_limit(&self, max_fee_per_gas: U256) -> Result<U256> {
+        let block = self
+            .client
+            .get_block(BlockNumber::Latest)
+            .await?
+            .context("latest block not found")?;
+        Ok(match self.gas.max_cost {
+            Some(budget) if !max_fee_per_gas.is_zero() => {
+                block.gas_limit.min(budget / max_fee_per_gas)
+            }
+            _ => block.gas_limit,
+        })
+    }
+
+    fn check_cost(&self, gas_limit: U256, max_fee_per_gas: U256) -> Result<()> {
+        let max_cost = gas_limit * max_fee_per_gas;
+        if let Some(budget) = self.gas.max_cost {
+            ensure!(
+                max_cost <= budget,
+                "transaction may cost up to {} ETH, above the budget of {} ETH",
+                format_ether(max_cost),
+                format_ether(budget)
+            );
+        }
+        Ok(())
+    }
+
+    fn tx_request(&self, calldata: Vec<u8>) -> Eip1559TransactionRequest {
+        Eip1559TransactionRequest::new()
+            .chain_id(self.chain_id)
+            .to(self.contract)
+            .from(self.signer.address())
+            .data(calldata)
+    }
+}
+
+/// Returns whether the error rejects a raw transaction that the node, or
+/// another one, already received.
+fn is_known_transaction(err: &ProviderError) -> bool {
+    err.as_error_response().map_or(false, |err| {
+        let message = err.message.to_lowercase();
+        ["already known", "known transaction", "nonce too low"]
+            .iter()
+            .any(|known| message.contains(known))
+    })
+}
+
+/// Converts the error of a call, decoding its revert data if it reverted.
+fn revert_error<E: MiddlewareError + 'static>(err: E) -> anyhow::Error {
+    match err
+        .as_error_response()
+        .and_then(JsonRpcError::as_revert_data)
+    {
+        Some(data) => {
+            anyhow::Error::new(RevertReason::decode(&data)).context("transaction would revert")
+        }
+        None => err.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use alloy_sol_types::SolError;
+    use ethers::utils::{keccak256, parse_units};
+    use serde_json::{json, Value};
+    use tokio::{sync::broadcast, task::JoinHandle};
+
+    use super::*;
+    use crate::{
+        mock_node::{method_not_found, serve_http, serve_ipc, Handler},
+        revert::IRiscZeroVerifier,
+    };
+
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const CONTRACT: &str = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+
+    /// State of a mock chain 31337, including the pending transactions in a
+    /// new block each time it is mined.
+    #[derive(Default)]
+    struct ChainState {
+        head: u64,
+        /// Number of reorgs, changing the hashes of the blocks.
+        fork: u64,
+        pending: Vec<TxHash>,
+        receipts: HashMap<TxHash, TransactionReceipt>,
+        /// Number of raw transactions received.
+        broadcasts: usize,
+        /// Revert data of the transactions and calls, if they revert.
+        revert: Option<Bytes>,
+        /// Whether raw transactions are answered as already known, as by a
+        /// node that received them from another one.
+        already_known: bool,
+    }
+
+    impl ChainState {
+        fn block_hash(&self, number: u64) -> H256 {
+            H256(keccak256(
+                [self.fork.to_be_bytes(), number.to_be_bytes()].concat(),
+            ))
+        }
+
+        fn block(&self, number: u64) -> Block<TxHash> {
+            Block {
+                number: Some(number.into()),
+                hash: Some(self.block_hash(number)),
+                gas_limit: 30_000_000.into(),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct Chain {
+        state: Arc<Mutex<ChainState>>,
+        heads: broadcast::Sender<Value>,
+    }
+
+    impl Chain {
+        fn new() -> Self {
+            Self {
+                state: Default::default(),
+                heads: broadcast::channel(16).0,
+            }
+        }
+
+        /// Mines a new block, including the pending transactions.
+        fn mine(&self) {
+            let mut state = self.state.lock().unwrap();
+            state.head += 1;
+            let head = state.head;
+            for hash in std::mem::take(&mut state.pending) {
+                let receipt = TransactionReceipt {
+                    transaction_hash: hash,
+                    block_number: Some(head.into()),
+                    block_hash: Some(state.block_hash(head)),
+                    status: Some(u64::from(state.revert.is_none()).into()),
+                    ..Default::default()
+                };
+                state.receipts.insert(hash, receipt);
+            }
+            self.heads
+                .send(serde_json::to_value(state.block(head)).unwrap())
+                .ok();
+        }
+
+        /// Replaces the head block with an empty block, dropping its
+        /// transactions rather than returning them to the mempool.
+        fn reorg(&self) {
+            let mut state = self.state.lock().unwrap();
+            let head = state.head;
+            state
+                .receipts
+                .retain(|_, receipt| receipt.block_number != Some(head.into()));
+            state.fork += 1;
+            self.heads
+                .send(serde_json::to_value(state.block(head)).unwrap())
+                .ok();
+        }
+
+        /// Mines a new block at the given interval, in the background.
+        fn start_mining(&self, interval: Duration) -> JoinHandle<()> {
+            let chain = self.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    chain.mine();
+                }
+            })
+        }
+
+        fn handler(&self) -> Handler {
+            let chain = self.clone();
+            Arc::new(move |method, params| chain.answer(method, params))
+        }
+
+        fn answer(&self, method: &str, params: &Value) -> Result<Value, Value> {
+            let mut state = self.state.lock().unwrap();
+            let result = match method {
+                "eth_chainId" => json!("0x7a69"),
+                "eth_blockNumber" => json!(U64::from(state.head)),
+                "eth_getTransactionCount" => json!("0x0"),
+                "eth_estimateGas" | "eth_call" => match &state.revert {
+                    Some(data) => {
+                        return Err(
+                            json!({ "code": 3, "message": "execution reverted", "data": data }),
+                        )
+                    }
+                    None if method == "eth_estimateGas" => json!(U256::from(100_000)),
+                    None => json!("0x"),
+                },
+                "eth_getBlockByNumber" => {
+                    // Tags, such as "latest", select the head.
+                    let number = serde_json::from_value::<U64>(params[0].clone())
+                        .map_or(state.head, |number| number.as_u64());
+                    if number > state.head {
+                        Value::Null
+                    } else {
+                        serde_json::to_value(state.block(number)).unwrap()
+                    }
+                }
+                "eth_sendRawTransaction" => {
+                    let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+                    let hash = H256(keccak256(&raw));
+                    state.pending.push(hash);
+                    state.broadcasts += 1;
+                    if state.already_known {
+                        return Err(json!({ "code": -32000, "message": "already known" }));
+                    }
+                    json!(hash)
+                }
+                "eth_getTransactionReceipt" => {
+                    let hash: TxHash = serde_json::from_value(params[0].clone()).unwrap();
+                    serde_json::to_value(state.receipts.get(&hash)).unwrap()
+                }
+                _ => return Err(method_not_found()),
+            };
+            Ok(result)
+        }
+    }
+
+    async fn tx_sender(rpc_urls: &[String]) -> TxSender {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        TxSender::new(31337, rpc_urls, wallet, CONTRACT)
+            .await
+            .unwrap()
+            .with_gas_options(GasOptions {
+                max_fee_per_gas: Some(parse_units(2, "gwei").unwrap().into()),
+                max_priority_fee_per_gas: Some(parse_units(1, "gwei").unwrap().into()),
+                gas_limit: Some(100_000.into()),
+                ..Default::default()
+            })
+    }
+
+    #[tokio::test]
+    async fn waits_for_new_heads_subscription() {
+        let chain = Chain::new();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.ipc");
+        serve_ipc(&path, chain.handler(), chain.heads.clone());
+        let _miner = chain.start_mining(Duration::from_millis(50));
+
+        // Polling would only check the receipt after the default 7 second
+        // interval, so confirming sooner shows that the new heads woke it up.
+        let tx_sender = tx_sender(&[path.display().to_string()])
+            .await
+            .with_confirmations(2);
+        assert!(tx_sender.client.get_interval() > Duration::from_secs(5));
+        let receipt = tokio::time::timeout(Duration::from_secs(5), tx_sender.send(vec![1, 2, 3]))
+            .await
+            .expect("confirmed before the polling interval")
+            .unwrap();
+        assert_eq!(receipt.status, Some(1.into()));
+    }
+
+    /// Returns the URL of a closed port.
+    async fn dead_node() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn confirms_with_backup_endpoint_down() {
+        let chain = Chain::new();
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let _miner = chain.start_mining(Duration::from_millis(20));
+
+        let mut tx_sender = tx_sender(&[url, dead_node().await]).await;
+        tx_sender.client.set_interval(Duration::from_millis(10));
+        let receipt = tokio::time::timeout(Duration::from_secs(5), tx_sender.send(vec![1, 2, 3]))
+            .await
+            .expect("confirmed by the healthy endpoint")
+            .unwrap();
+        assert_eq!(receipt.status, Some(1.into()));
+
+        // An explicit quorum keeps requiring both endpoints.
+        let tx_sender = tx_sender.with_quorum(2).unwrap();
+        tokio::time::timeout(Duration::from_millis(500), tx_sender.send(vec![1, 2, 3]))
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn broadcasts_again_after_reorg() {
+        let chain = Chain::new();
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let mut tx_sender = tx_sender(&[url]).await.with_confirmations(3);
+        tx_sender.client.set_interval(Duration::from_millis(10));
+        let sending = tokio::spawn(async move { tx_sender.send(vec![1, 2, 3]).await });
+
+        // Include the transaction, and let the sender see it, before a reorg
+        // drops it.
+        while chain.state.lock().unwrap().pending.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        chain.mine();
+        let dropped_block = chain.state.lock().unwrap().block_hash(1);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        chain.reorg();
+
+        let _miner = chain.start_mining(Duration::from_millis(20));
+        let receipt = tokio::time::timeout(Duration::from_secs(5), sending)
+            .await
+            .expect("confirmed after the reorg")
+            .unwrap()
+            .unwrap();
+        assert_ne!(receipt.block_hash, Some(dropped_block));
+        assert_eq!(chain.state.lock().unwrap().broadcasts, 2);
+    }
+
+    #[tokio::test]
+    async fn times_out_unconfirmed_transaction() {
+        // The chain never mines the transaction.
+        let chain = Chain::new();
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let mut tx_sender = tx_sender(&[url])
+            .await
+            .with_confirmation_timeout(Duration::from_millis(200));
+        tx_sender.client.set_interval(Duration::from_millis(10));
+
+        let err = tokio::time::timeout(Duration::from_secs(5), tx_sender.send(vec![1, 2, 3]))
+            .await
+            .expect("timed out by the sender")
+            .unwrap_err();
+        let hash = chain.state.lock().unwrap().pending[0];
+        assert!(
+            err.to_string().starts_with(&format!(
+                "transaction {hash:?} was not confirmed within 200ms"
+            )),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn adds_gas_margin_to_estimate() {
+        let chain = Chain::new();
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let tx_sender = tx_sender(&[url]).await;
+        let gas = GasOptions {
+            gas_limit: None,
+            gas_margin_percent: 20,
+            ..tx_sender.gas.clone()
+        };
+        let tx_sender = tx_sender.with_gas_options(gas);
+
+        let tx = tx_sender.transaction(vec![1, 2, 3], false).await.unwrap();
+        assert_eq!(tx.gas, Some(120_000.into()));
+        assert_eq!(tx.max_fee_per_gas, Some(2_000_000_000u64.into()));
+        assert_eq!(tx.max_priority_fee_per_gas, Some(1_000_000_000u64.into()));
+
+        // A set gas limit is used as is.
+        let gas = GasOptions {
+            gas_limit: Some(50_000.into()),
+            ..tx_sender.gas.clone()
+        };
+        let tx_sender = tx_sender.with_gas_options(gas);
+        let tx = tx_sender.transaction(vec![1, 2, 3], false).await.unwrap();
+        assert_eq!(tx.gas, Some(50_000.into()));
+    }
+
+    #[tokio::test]
+    async fn aborts_above_max_cost() {
+        let chain = Chain::new();
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let tx_sender = tx_sender(&[url]).await;
+
+        // 100,000 gas at 2 gwei costs up to 0.0002 ETH.
+        let gas = GasOptions {
+            max_cost: Some(parse_units("0.0002", "ether").unwrap().into()),
+            ..tx_sender.gas.clone()
+        };
+        let tx_sender = tx_sender.with_gas_options(gas);
+        tx_sender.transaction(vec![1, 2, 3], false).await.unwrap();
+
+        let gas = GasOptions {
+            max_cost: Some(parse_units("0.00019", "ether").unwrap().into()),
+            ..tx_sender.gas.clone()
+        };
+        let tx_sender = tx_sender.with_gas_options(gas);
+        let err = tx_sender.send(vec![1, 2, 3]).await.unwrap_err();
+        let err = err.to_string();
+        assert!(
+            err.starts_with("transaction may cost up to 0.0002")
+                && err.contains("above the budget of 0.00019"),
+            "{err}"
+        );
+        assert_eq!(chain.state.lock().unwrap().broadcasts, 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_priority_fee_above_max_fee() {
+        let chain = Chain::new();
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let tx_sender = tx_sender(&[url]).await;
+        let gas = GasOptions {
+            max_priority_fee_per_gas: Some(parse_units(3, "gwei").unwrap().into()),
+            ..tx_sender.gas.clone()
+        };
+        let tx_sender = tx_sender.with_gas_options(gas);
+
+        let err = tx_sender.send(vec![1, 2, 3]).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "max priority fee of 3000000000 wei is above the max fee per gas of 2000000000 wei"
+        );
+        assert_eq!(chain.state.lock().unwrap().broadcasts, 0);
+    }
+
+    #[tokio::test]
+    async fn fails_on_reverted_transaction() {
+        // The node does not support tracing, so the reason comes from a replay.
+        let chain = Chain::new();
+        chain.state.lock().unwrap().revert =
+            Some(IRiscZeroVerifier::VerificationFailed {}.abi_encode().into());
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let _miner = chain.start_mining(Duration::from_millis(20));
+        let mut tx_sender = tx_sender(&[url]).await;
+        tx_sender.client.set_interval(Duration::from_millis(10));
+
+        let err = tokio::time::timeout(Duration::from_secs(5), tx_sender.send(vec![1, 2, 3]))
+            .await
+            .expect("mined")
+            .unwrap_err();
+        let state = chain.state.lock().unwrap();
+        let receipt = state.receipts.values().next().unwrap();
+        let (hash, block) = (receipt.transaction_hash, receipt.block_number.unwrap() - 1);
+        assert_eq!(
+            format!("{err:#}"),
+            format!(
+                "transaction {hash:?} reverted: approximate reason, replayed on top of block {block}: \
+                 seal rejected: verification failed"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn forces_reverting_transaction() {
+        let chain = Chain::new();
+        chain.state.lock().unwrap().revert =
+            Some(IRiscZeroVerifier::VerificationFailed {}.abi_encode().into());
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let _miner = chain.start_mining(Duration::from_millis(20));
+        let mut tx_sender = tx_sender(&[url]).await;
+        let gas = GasOptions {
+            gas_limit: None,
+            ..tx_sender.gas.clone()
+        };
+        tx_sender = tx_sender.with_gas_options(gas);
+        tx_sender.client.set_interval(Duration::from_millis(10));
+
+        let err = tx_sender
+            .simulate_and_send(vec![1, 2, 3], false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("use --force"), "{err}");
+        assert_eq!(chain.state.lock().unwrap().broadcasts, 0);
+
+        // Without an estimate, the gas limit is the block gas limit.
+        let tx = tx_sender.transaction(vec![1, 2, 3], true).await.unwrap();
+        assert_eq!(tx.gas, Some(30_000_000.into()));
+
+        let err = tokio::time::timeout(
+            Duration::from_secs(5),
+            tx_sender.simulate_and_send(vec![1, 2, 3], true),
+        )
+        .await
+        .expect("mined")
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("reverted"), "{err:#}");
+        assert_eq!(chain.state.lock().unwrap().broadcasts, 1);
+    }
+
+    #[tokio::test]
+    async fn waits_for_already_known_transaction() {
+        let chain = Chain::new();
+        chain.state.lock().unwrap().already_known = true;
+        let url = format!("http://{}", serve_http(chain.handler()).await);
+        let _miner = chain.start_mining(Duration::from_millis(20));
+        let dir = tempfile::tempdir().unwrap();
+        let nonces = NonceStore::new(dir.path().join("nonces.json"));
+        let mut tx_sender = tx_sender(&[url]).await.with_nonce_store(nonces.clone());
+        tx_sender.client.set_interval(Duration::from_millis(10));
+
+        let receipt = tokio::time::timeout(Duration::from_secs(5), tx_sender.send(vec![1, 2, 3]))
+            .await
+            .expect("confirmed although already known")
+            .unwrap();
+        let last = nonces
+            .last(31337, TxSigner::address(&tx_sender.signer))
+            .unwrap()
+            .unwrap();
+        assert_eq!(last.hash, receipt.transaction_hash);
+        assert_eq!(chain.state.lock().unwrap().broadcasts, 1);
+    }
+}
@@ -21,6 +21,8 @@ import {ImageID} from "./ImageID.sol"; // auto-generated contract after running
 
 /// @title A starter application using RISC Zero.
 /// @notice This basic application holds a number, guaranteed to be even.
+///         The number is the result of `x^e mod n` for a private `x`, proven on the client
+///         and composed into the `is_even` proof.
 /// @dev This contract demonstrates one pattern for offloading the computation of an expensive
 ///      or difficult to implement function to a RISC Zero guest running on Bonsai.
 contract EvenNumber {
@@ -37,18 +39,32 @@ contract EvenNumber {
     ///         It can be set by calling the `set` function.
     uint256 public number;
 
+    /// @notice Public modulus `n` of the power_modulus computation that produced `number`.
+    uint256 public modulus;
+
+    /// @notice Public exponent `e` of the power_modulus computation that produced `number`.
+    uint256 public exponent;
+
+    /// @notice Hiding commitment `sha256(x || salt)` to the private `x` that produced `number`,
+    ///         or zero if the client did not commit to `x`.
+    bytes32 public commitment;
+
     /// @notice Initialize the contract, binding it to a specified RISC Zero verifier.
     constructor(IRiscZeroVerifier _verifier) {
         verifier = _verifier;
         number = 0;
     }
 
-    /// @notice Set the even number stored on the contract. Requires a RISC Zero proof that the number is even.
-    function set(uint256 x, bytes calldata seal) public {
+    /// @notice Set the even number stored on the contract. Requires a RISC Zero proof that `y = x^e mod n`
+    ///         for some private `x` committed to by `_commitment`, and that `y` is even.
+    function set(uint256 n, uint256 e, uint256 y, bytes32 _commitment, bytes calldata seal) public {
         // Construct the expected journal data. Verify will fail if journal does not match.
-        bytes memory journal = abi.encode(x);
+        bytes memory journal = abi.encode(n, e, y, _commitment);
         verifier.verify(seal, imageId, sha256(journal));
-        number = x;
+        modulus = n;
+        exponent = e;
+        commitment = _commitment;
+        number = y;
     }
 
     /// @notice Returns the number stored.
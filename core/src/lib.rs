@@ -0,0 +1,295 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Types shared by the host and the zkVM guests, so that what the host writes
+//! into an `ExecutorEnv` cannot drift from what the guests read back.
+//!
+//! Guest inputs, and the power_modulus journal, use the [risc0 serde] format
+//! read by `env::read()` and written by `env::commit()`. The is_even journal
+//! is Solidity ABI encoded, for easy decoding in the app contract.
+//!
+//! [risc0 serde]: risc0_zkvm::serde
+
+use std::fmt;
+
+use alloy_primitives::{B256, U256};
+use alloy_sol_types::{sol, SolValue};
+use risc0_zkvm::{
+    serde::{from_slice, to_vec, Error},
+    sha::{Digest, Impl, Sha256},
+};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Input to the power_modulus guest.
+///
+/// The private `x` and `salt` are redacted from the `Debug` output, and zeroed
+/// when the input is dropped.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerModulusInput {
+    /// Public modulus.
+    pub n: U256,
+    /// Public exponent.
+    pub e: U256,
+    /// Private value, never revealed in the journal.
+    pub x: U256,
+    /// Optional private random salt. When set, the guest commits to
+    /// `sha256(x || salt)`, so that separate proofs about the same `x` can be
+    /// linked without revealing it.
+    pub salt: Option<[u8; 32]>,
+}
+
+impl PowerModulusInput {
+    /// Encodes the input into the words read by the guest with `env::read()`.
+    pub fn encode(&self) -> Result<Vec<u32>, Error> {
+        to_vec(self)
+    }
+
+    /// Decodes the input from the words read by the guest.
+    pub fn decode(words: &[u32]) -> Result<Self, Error> {
+        from_slice(words)
+    }
+
+    /// Checks that the input describes a meaningful `x^e mod n`.
+    ///
+    /// The same rules are enforced by the guest, and may be checked on the host
+    /// before spending time proving.
+    pub fn validate(&self) -> Result<(), PowerModulusError> {
+        if self.n <= U256::from(1) {
+            return Err(PowerModulusError::ModulusTooSmall);
+        }
+        if self.x >= self.n {
+            return Err(PowerModulusError::ValueOutOfRange);
+        }
+        if self.e.is_zero() {
+            return Err(PowerModulusError::ZeroExponent);
+        }
+        Ok(())
+    }
+
+    /// Returns the hiding commitment to `x`, if a salt is set.
+    pub fn commitment(&self) -> Option<Digest> {
+        self.salt.as_ref().map(|salt| commit_to_x(self.x, salt))
+    }
+}
+
+impl fmt::Debug for PowerModulusInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PowerModulusInput")
+            .field("n", &self.n)
+            .field("e", &self.e)
+            .field("x", &"<redacted>")
+            .field("salt", &self.salt.map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl Drop for PowerModulusInput {
+    fn drop(&mut self) {
+        // SAFETY: Any value of the limbs is a valid U256.
+        unsafe { self.x.as_limbs_mut() }.zeroize();
+        self.salt.zeroize();
+    }
+}
+
+/// Computes `sha256(x || salt)`, with `x` as 32 big-endian bytes.
+pub fn commit_to_x(x: U256, salt: &[u8; 32]) -> Digest {
+    let mut preimage = Zeroizing::new([0u8; 64]);
+    preimage[..32].copy_from_slice(&x.to_be_bytes::<32>());
+    preimage[32..].copy_from_slice(salt);
+    *Impl::hash_bytes(preimage.as_slice())
+}
+
+/// Reasons a [PowerModulusInput] is rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerModulusError {
+    /// `n` is 0 or 1, for which `x^e mod n` says nothing about `x`.
+    ModulusTooSmall,
+    /// `x` is not less than `n`.
+    ValueOutOfRange,
+    /// `e` is 0, for which `x^e mod n` says nothing about `x`.
+    ZeroExponent,
+}
+
+impl fmt::Display for PowerModulusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ModulusTooSmall => write!(f, "modulus must be greater than 1"),
+            Self::ValueOutOfRange => write!(f, "x must be less than the modulus"),
+            Self::ZeroExponent => write!(f, "exponent must be greater than 0"),
+        }
+    }
+}
+
+impl std::error::Error for PowerModulusError {}
+
+/// Journal committed by the power_modulus guest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerModulusJournal {
+    /// Public modulus.
+    pub n: U256,
+    /// Public exponent.
+    pub e: U256,
+    /// Result of `x^e mod n`.
+    pub y: U256,
+    /// Hiding commitment to `x`, present when the input carried a salt.
+    pub commitment: Option<Digest>,
+}
+
+impl PowerModulusJournal {
+    /// Encodes the journal into the bytes committed with `env::commit()`.
+    ///
+    /// This is also the journal passed to `env::verify()` when composing a
+    /// power_modulus receipt.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        Ok(to_vec(self)?
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .collect())
+    }
+
+    /// Decodes the journal from the bytes of a power_modulus receipt.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        from_slice(bytes)
+    }
+}
+
+/// Input to the is_even guest.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IsEvenInput {
+    /// Journal of the power_modulus receipt added as an assumption.
+    pub assumption: PowerModulusJournal,
+    /// Number to check for parity. The guest rejects any number that differs
+    /// from the result committed in `assumption`.
+    pub number: U256,
+}
+
+impl IsEvenInput {
+    /// Creates the input checking the result committed in the given journal.
+    pub fn new(assumption: PowerModulusJournal) -> Self {
+        Self {
+            assumption,
+            number: assumption.y,
+        }
+    }
+
+    /// Encodes the input into the words read by the guest with `env::read()`.
+    pub fn encode(&self) -> Result<Vec<u32>, Error> {
+        to_vec(self)
+    }
+
+    /// Decodes the input from the words read by the guest.
+    pub fn decode(words: &[u32]) -> Result<Self, Error> {
+        from_slice(words)
+    }
+}
+
+sol! {
+    /// Journal committed by the is_even guest, as
+    /// `abi.encode(n, e, y, commitment)`.
+    #[derive(Debug, PartialEq, Eq)]
+    struct IsEvenJournal {
+        /// Public modulus.
+        uint256 n;
+        /// Public exponent.
+        uint256 e;
+        /// Even result of `x^e mod n`.
+        uint256 y;
+        /// Hiding commitment `sha256(x || salt)` to `x`, or zero when the
+        /// input carried no salt.
+        bytes32 commitment;
+    }
+}
+
+impl IsEvenJournal {
+    /// Encodes the journal using Solidity ABI, as expected by the app contract.
+    pub fn encode(&self) -> Vec<u8> {
+        self.abi_encode()
+    }
+
+    /// Decodes the journal from the bytes of an is_even receipt.
+    pub fn decode(bytes: &[u8]) -> Result<Self, alloy_sol_types::Error> {
+        Self::abi_decode(bytes, true)
+    }
+}
+
+impl From<PowerModulusJournal> for IsEvenJournal {
+    fn from(journal: PowerModulusJournal) -> Self {
+        Self {
+            n: journal.n,
+            e: journal.e,
+            y: journal.y,
+            commitment: journal.commitment.map_or(B256::ZERO, |commitment| {
+                B256::from_slice(commitment.as_bytes())
+            }),
+        }
+    }
+}
+
+/// Computes `x^e mod n`.
+///
+/// Panics if `n` is zero.
+pub fn pow_mod(x: U256, e: U256, n: U256) -> U256 {
+    assert!(!n.is_zero(), "modulus must be non-zero");
+    let mut x = x.reduce_mod(n);
+    let mut z = U256::from(1).reduce_mod(n);
+
+    // Apply a simple implementation of exponentiation by squaring
+    // https://en.wikipedia.org/wiki/Exponentiation_by_squaring
+    for i in 0..e.bit_len() {
+        if e.bit(i) {
+            z = mul_mod(z, x, n);
+        }
+        x = mul_mod(x, x, n);
+    }
+    z
+}
+
+/// Computes `a * b mod n` using the zkVM's bigint accelerator.
+#[cfg(target_os = "zkvm")]
+fn mul_mod(a: U256, b: U256, n: U256) -> U256 {
+    use risc0_zkvm_platform::syscall::{bigint, sys_bigint};
+
+    let mut result = [0u32; bigint::WIDTH_WORDS];
+    // SAFETY: All pointers reference live, properly sized word arrays.
+    unsafe {
+        sys_bigint(
+            &mut result,
+            bigint::OP_MULTIPLY,
+            &to_words(a),
+            &to_words(b),
+            &to_words(n),
+        );
+    }
+    let result = U256::from_limbs(core::array::from_fn(|i| {
+        u64::from(result[2 * i]) | (u64::from(result[2 * i + 1]) << 32)
+    }));
+    // The bigint circuit only constrains the result to 256 bits, so check that
+    // the prover returned the reduced one, rather than adding multiples of n.
+    assert!(result < n, "bigint result not reduced");
+    result
+}
+
+/// Computes `a * b mod n` in software, outside of the zkVM.
+#[cfg(not(target_os = "zkvm"))]
+fn mul_mod(a: U256, b: U256, n: U256) -> U256 {
+    a.mul_mod(b, n)
+}
+
+/// Splits the value into little-endian words, as expected by `sys_bigint`.
+#[cfg(target_os = "zkvm")]
+fn to_words(value: U256) -> [u32; 8] {
+    let limbs = value.as_limbs();
+    core::array::from_fn(|i| (limbs[i / 2] >> (32 * (i % 2))) as u32)
+}
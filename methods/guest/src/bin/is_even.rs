@@ -12,33 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::Read;
-
-use alloy_primitives::U256;
-use alloy_sol_types::SolValue;
+use composition_core::{IsEvenInput, IsEvenJournal};
 use risc0_zkvm::guest::env;
-use risc0_zkvm::serde;
 
 // Hack to get methods included from build step in /methods
 // include!(env!("METHODS"));
 include!(concat!(env!("OUT_DIR"), "/methods.rs"));
 
 fn main() {
-    // Verify Local proof's receipt remotely
-    let local_committed: (u64, u64, u64) = env::read();
-    env::verify(POWER_MODULUS_ID, &serde::to_vec(&local_committed).unwrap());
-
     // Read the input data for this application.
-    let mut input_bytes = Vec::<u8>::new();
-    env::stdin().read_to_end(&mut input_bytes).unwrap();
-    // Decode and parse the input
-    let number = <U256>::abi_decode(&input_bytes, true).unwrap();
+    let input: IsEvenInput = env::read();
+
+    // Verify Local proof's receipt remotely.
+    let journal = input.assumption;
+    env::verify(POWER_MODULUS_ID, &journal.encode().unwrap()).unwrap();
+
+    // Bind the input to the verified result, so that a receipt for one value
+    // cannot be used to vouch for the parity of another.
+    assert_eq!(
+        input.number, journal.y,
+        "number does not match the verified power_modulus result"
+    );
 
     // Run the computation.
     // In this case, asserting that the provided number is even.
-    assert!(!number.bit(0), "number is not even");
+    assert!(!input.number.bit(0), "number is not even");
 
     // Commit the journal that will be received by the application contract.
     // Journal is encoded using Solidity ABI for easy decoding in the app contract.
-    env::commit_slice(number.abi_encode().as_slice());
+    env::commit_slice(&IsEvenJournal::from(journal).encode());
 }
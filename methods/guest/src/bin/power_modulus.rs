@@ -12,31 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use composition_core::{pow_mod, PowerModulusInput, PowerModulusJournal};
 use risc0_zkvm::guest::env;
 
 fn main() {
     // n and e are the public modulus and exponent respectively.
     // x value that will be kept private.
-    let (n, e, x): (u64, u64, u64) = env::read();
+    let input: PowerModulusInput = env::read();
 
-    // Commit n, e, and x^e mod n.
-    env::commit(&(n, e, pow_mod(x, e, n)));
-}
-
-/// Compute x^e (mod n)
-pub fn pow_mod(x: u64, mut e: u64, n: u64) -> u64 {
-    let mut x = x as u128;
-    let n = n as u128;
-    let mut z = 1u128;
-
-    // Apply a simple implementation of exponentiation by squaring
-    // https://en.wikipedia.org/wiki/Exponentiation_by_squaring
-    while e > 0 {
-        if e % 2 == 1 {
-            z = (z * x) % n
-        }
-        e >>= 1;
-        x = (x * x) % n;
+    // Reject inputs for which the result would say nothing meaningful about x.
+    if let Err(err) = input.validate() {
+        panic!("invalid power_modulus input: {err}");
     }
-    return z as u64;
-}
\ No newline at end of file
+
+    // Commit n, e, x^e mod n, and, if salted, sha256(x || salt).
+    env::commit(&PowerModulusJournal {
+        n: input.n,
+        e: input.e,
+        y: pow_mod(input.x, input.e, input.n),
+        commitment: input.commitment(),
+    });
+}
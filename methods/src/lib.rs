@@ -17,35 +17,234 @@ include!(concat!(env!("OUT_DIR"), "/methods.rs"));
 
 #[cfg(test)]
 mod tests {
-    use alloy_primitives::U256;
-    use alloy_sol_types::SolValue;
-    use risc0_zkvm::{default_executor, ExecutorEnv};
+    use alloy_primitives::{B256, U256};
+    use composition_core::{
+        commit_to_x, IsEvenInput, IsEvenJournal, PowerModulusError, PowerModulusInput,
+        PowerModulusJournal,
+    };
+    use risc0_zkvm::{default_executor, sha::Digestible, ExecutorEnv, ReceiptClaim};
 
-    #[test]
-    fn proves_even_number() {
-        let even_number = U256::from(1304);
+    /// Executes the power_modulus guest, returning its claim, to be used as an
+    /// unresolved assumption, and its decoded journal.
+    fn power_modulus(n: U256, e: U256, x: U256) -> (ReceiptClaim, PowerModulusJournal) {
+        power_modulus_with_input(&PowerModulusInput {
+            n,
+            e,
+            x,
+            salt: None,
+        })
+    }
 
+    fn power_modulus_with_input(input: &PowerModulusInput) -> (ReceiptClaim, PowerModulusJournal) {
         let env = ExecutorEnv::builder()
-            .write_slice(&even_number.abi_encode())
+            .write(input)
+            .unwrap()
             .build()
             .unwrap();
 
+        // NOTE: Use the executor to run tests without proving.
+        let session_info = default_executor()
+            .execute(env, super::POWER_MODULUS_ELF)
+            .unwrap();
+
+        let claim = ReceiptClaim::ok(super::POWER_MODULUS_ID, session_info.journal.bytes.clone());
+        let journal = PowerModulusJournal::decode(&session_info.journal.bytes).unwrap();
+        (claim, journal)
+    }
+
+    /// Builds the is_even `ExecutorEnv`, in the same way as the publisher.
+    fn is_even_env<'a>(claim: ReceiptClaim, input: &IsEvenInput) -> ExecutorEnv<'a> {
+        ExecutorEnv::builder()
+            .add_assumption(claim)
+            .write(input)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn commits_power_modulus() {
+        // 12^3 mod 1000 = 728
+        let (claim, journal) = power_modulus(U256::from(1000), U256::from(3), U256::from(12));
+        assert_eq!(
+            journal,
+            PowerModulusJournal {
+                n: U256::from(1000),
+                e: U256::from(3),
+                y: U256::from(728),
+                commitment: None,
+            }
+        );
+
+        // The claim must match the journal the is_even guest passes to `env::verify`.
+        let expected = ReceiptClaim::ok(super::POWER_MODULUS_ID, journal.encode().unwrap());
+        assert_eq!(claim.digest(), expected.digest());
+    }
+
+    #[test]
+    fn commits_power_modulus_of_large_operands() {
+        let n = U256::MAX - U256::from(188); // 2^256 - 189, prime
+        let e = U256::from(65537);
+        let x = U256::from(0x123456789abcdef0123456789abcdef_u128);
+        let (_, journal) = power_modulus(n, e, x);
+
+        let expected: U256 = "0xcfe46a14bade79168b729ebeb02954d62d7489f5fa2f8c8118aba8aa2a14cdbc"
+            .parse()
+            .unwrap();
+        assert_eq!(journal.y, expected);
+        assert_eq!(composition_core::pow_mod(x, e, n), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid power_modulus input: modulus must be greater than 1")]
+    fn rejects_zero_modulus() {
+        power_modulus(U256::ZERO, U256::from(3), U256::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid power_modulus input: modulus must be greater than 1")]
+    fn rejects_unit_modulus() {
+        power_modulus(U256::from(1), U256::from(3), U256::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid power_modulus input: x must be less than the modulus")]
+    fn rejects_value_out_of_range() {
+        power_modulus(U256::from(1000), U256::from(3), U256::from(1012));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid power_modulus input: exponent must be greater than 0")]
+    fn rejects_zero_exponent() {
+        power_modulus(U256::from(1000), U256::ZERO, U256::from(12));
+    }
+
+    #[test]
+    fn validates_on_host() {
+        let input = PowerModulusInput {
+            n: U256::from(1000),
+            e: U256::from(3),
+            x: U256::from(12),
+            salt: None,
+        };
+        assert_eq!(input.validate(), Ok(()));
+        assert_eq!(
+            PowerModulusInput {
+                n: U256::from(1),
+                ..input.clone()
+            }
+            .validate(),
+            Err(PowerModulusError::ModulusTooSmall)
+        );
+        assert_eq!(
+            PowerModulusInput {
+                x: U256::from(1000),
+                ..input.clone()
+            }
+            .validate(),
+            Err(PowerModulusError::ValueOutOfRange)
+        );
+        assert_eq!(
+            PowerModulusInput {
+                e: U256::ZERO,
+                ..input
+            }
+            .validate(),
+            Err(PowerModulusError::ZeroExponent)
+        );
+    }
+
+    #[test]
+    fn commits_to_salted_x() {
+        let input = PowerModulusInput {
+            n: U256::from(1000),
+            e: U256::from(3),
+            x: U256::from(12),
+            salt: Some([7u8; 32]),
+        };
+        let (_, journal) = power_modulus_with_input(&input);
+        assert_eq!(
+            journal.commitment,
+            Some(commit_to_x(U256::from(12), &[7u8; 32]))
+        );
+
+        // Proofs about the same x and salt are linkable, whatever the public parameters.
+        let (_, other) = power_modulus_with_input(&PowerModulusInput {
+            n: U256::from(997),
+            e: U256::from(5),
+            ..input.clone()
+        });
+        assert_eq!(other.commitment, journal.commitment);
+
+        // A fresh salt gives an unlinkable commitment.
+        let (_, fresh) = power_modulus_with_input(&PowerModulusInput {
+            salt: Some([8u8; 32]),
+            ..input
+        });
+        assert_ne!(fresh.commitment, journal.commitment);
+    }
+
+    #[test]
+    fn proves_even_number_with_commitment() {
+        let (claim, journal) = power_modulus_with_input(&PowerModulusInput {
+            n: U256::from(1000),
+            e: U256::from(3),
+            x: U256::from(12),
+            salt: Some([7u8; 32]),
+        });
+        let env = is_even_env(claim, &IsEvenInput::new(journal));
+
         // NOTE: Use the executor to run tests without proving.
         let session_info = default_executor().execute(env, super::IS_EVEN_ELF).unwrap();
 
-        let x = U256::abi_decode(&session_info.journal.bytes, true).unwrap();
-        assert_eq!(x, even_number);
+        let journal = IsEvenJournal::decode(&session_info.journal.bytes).unwrap();
+        assert_eq!(journal.y, U256::from(728));
+        assert_eq!(
+            journal.commitment.as_slice(),
+            commit_to_x(U256::from(12), &[7u8; 32]).as_bytes()
+        );
+    }
+
+    #[test]
+    fn proves_even_number() {
+        let (claim, journal) = power_modulus(U256::from(1000), U256::from(3), U256::from(12));
+        let env = is_even_env(claim, &IsEvenInput::new(journal));
+
+        // NOTE: Use the executor to run tests without proving.
+        let session_info = default_executor().execute(env, super::IS_EVEN_ELF).unwrap();
+
+        let journal = IsEvenJournal::decode(&session_info.journal.bytes).unwrap();
+        assert_eq!(
+            journal,
+            IsEvenJournal {
+                n: U256::from(1000),
+                e: U256::from(3),
+                y: U256::from(728),
+                commitment: B256::ZERO,
+            }
+        );
     }
 
     #[test]
     #[should_panic(expected = "number is not even")]
     fn rejects_odd_number() {
-        let odd_number = U256::from(75);
+        // 11^3 mod 1000 = 331
+        let (claim, journal) = power_modulus(U256::from(1000), U256::from(3), U256::from(11));
+        let env = is_even_env(claim, &IsEvenInput::new(journal));
 
-        let env = ExecutorEnv::builder()
-            .write_slice(&odd_number.abi_encode())
-            .build()
-            .unwrap();
+        // NOTE: Use the executor to run tests without proving.
+        default_executor().execute(env, super::IS_EVEN_ELF).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "number does not match the verified power_modulus result")]
+    fn rejects_unrelated_number() {
+        let (claim, journal) = power_modulus(U256::from(1000), U256::from(3), U256::from(11));
+        let input = IsEvenInput {
+            assumption: journal,
+            number: U256::from(1304),
+        };
+        let env = is_even_env(claim, &input);
 
         // NOTE: Use the executor to run tests without proving.
         default_executor().execute(env, super::IS_EVEN_ELF).unwrap();
@@ -16,35 +16,85 @@
 
 pragma solidity ^0.8.20;
 
-import {RiscZeroCheats} from "risc0/test/RiscZeroCheats.sol";
 import {console2} from "forge-std/console2.sol";
 import {Test} from "forge-std/Test.sol";
-import {IRiscZeroVerifier} from "risc0/IRiscZeroVerifier.sol";
+import {Receipt as RiscZeroReceipt} from "risc0/IRiscZeroVerifier.sol";
+import {RiscZeroMockVerifier} from "risc0/test/RiscZeroMockVerifier.sol";
 import {EvenNumber} from "../contracts/EvenNumber.sol";
-import {Elf} from "./Elf.sol"; // auto-generated contract after running `cargo build`.
+import {ImageID} from "../contracts/ImageID.sol";
 
-contract EvenNumberTest is RiscZeroCheats, Test {
+// NOTE: The is_even guest verifies a power_modulus receipt as an assumption, which the `prove`
+// cheatcode cannot supply. These tests use the mock verifier to produce seals for known journals.
+contract EvenNumberTest is Test {
+    RiscZeroMockVerifier public verifier;
     EvenNumber public evenNumber;
 
     function setUp() public {
-        IRiscZeroVerifier verifier = deployRiscZeroVerifier();
+        verifier = new RiscZeroMockVerifier(bytes4(0));
         evenNumber = new EvenNumber(verifier);
         assertEq(evenNumber.get(), 0);
     }
 
     function test_SetEven() public {
-        uint256 number = 12345678;
-        (bytes memory journal, bytes memory seal) = prove(Elf.IS_EVEN_PATH, abi.encode(number));
+        // 12^3 mod 1000 = 728
+        uint256 n = 1000;
+        uint256 e = 3;
+        uint256 y = 728;
+        RiscZeroReceipt memory receipt =
+            verifier.mockProve(ImageID.IS_EVEN_ID, sha256(abi.encode(n, e, y, bytes32(0))));
 
-        evenNumber.set(abi.decode(journal, (uint256)), seal);
-        assertEq(evenNumber.get(), number);
+        evenNumber.set(n, e, y, bytes32(0), receipt.seal);
+        assertEq(evenNumber.get(), y);
+        assertEq(evenNumber.modulus(), n);
+        assertEq(evenNumber.exponent(), e);
+        assertEq(evenNumber.commitment(), bytes32(0));
+    }
+
+    function test_SetWithCommitment() public {
+        uint256 n = 1000;
+        uint256 e = 3;
+        uint256 y = 728;
+        bytes32 commitment = sha256(abi.encodePacked(uint256(12), bytes32(uint256(7))));
+        RiscZeroReceipt memory receipt =
+            verifier.mockProve(ImageID.IS_EVEN_ID, sha256(abi.encode(n, e, y, commitment)));
+
+        evenNumber.set(n, e, y, commitment, receipt.seal);
+        assertEq(evenNumber.get(), y);
+        assertEq(evenNumber.commitment(), commitment);
     }
 
     function test_SetZero() public {
-        uint256 number = 0;
-        (bytes memory journal, bytes memory seal) = prove(Elf.IS_EVEN_PATH, abi.encode(number));
+        // 10^2 mod 100 = 0
+        uint256 n = 100;
+        uint256 e = 2;
+        uint256 y = 0;
+        RiscZeroReceipt memory receipt =
+            verifier.mockProve(ImageID.IS_EVEN_ID, sha256(abi.encode(n, e, y, bytes32(0))));
+
+        evenNumber.set(n, e, y, bytes32(0), receipt.seal);
+        assertEq(evenNumber.get(), y);
+    }
+
+    function test_RejectsMismatchedJournal() public {
+        uint256 n = 1000;
+        uint256 e = 3;
+        RiscZeroReceipt memory receipt =
+            verifier.mockProve(ImageID.IS_EVEN_ID, sha256(abi.encode(n, e, uint256(728), bytes32(0))));
+
+        // A seal for one result must not vouch for another.
+        vm.expectRevert();
+        evenNumber.set(n, e, 730, bytes32(0), receipt.seal);
+    }
+
+    function test_RejectsMismatchedCommitment() public {
+        uint256 n = 1000;
+        uint256 e = 3;
+        uint256 y = 728;
+        RiscZeroReceipt memory receipt =
+            verifier.mockProve(ImageID.IS_EVEN_ID, sha256(abi.encode(n, e, y, bytes32(0))));
 
-        evenNumber.set(abi.decode(journal, (uint256)), seal);
-        assertEq(evenNumber.get(), number);
+        // A seal without a commitment must not vouch for one.
+        vm.expectRevert();
+        evenNumber.set(n, e, y, bytes32(uint256(1)), receipt.seal);
     }
 }